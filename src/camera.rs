@@ -1,29 +1,278 @@
 
 use nalgebra_glm::Vec3;
 use std::f32::consts::PI;
+use crate::random::Rng;
+use crate::ray::Ray;
+
+pub const DEFAULT_FOV: f32 = PI / 4.0;  // Campo de visión vertical si ni la escena ni la línea de comandos lo indican
+const MIN_ZOOM: f32 = 0.25;
+const MAX_ZOOM: f32 = 8.0;
+
+// Forma de la apertura del lente, que es la forma que toman los desenfoques (bokeh)
+#[derive(Clone)]
+pub enum Bokeh {
+    Circle,
+    Polygon(Fan),  // Diafragma de hojas rectas; se arma con `Bokeh::polygon`
+    Custom(Fan),
+}
+
+impl Bokeh {
+    pub fn polygon(blades: u32, rotation: f32) -> Self {
+        let blades = blades.max(3);
+        let outline = (0..blades)
+            .map(|i| {
+                let angle = rotation + i as f32 * 2.0 * PI / blades as f32;
+                (angle.cos(), angle.sin())
+            })
+            .collect();
+        Bokeh::Polygon(Fan::new(outline))
+    }
+
+    // Contorno en el círculo unitario; debe ser estrellado respecto al centro (estrella, corazón...)
+    pub fn custom(outline: Vec<(f32, f32)>) -> Self {
+        Bokeh::Custom(Fan::new(outline))
+    }
+
+    // Punto uniforme dentro de la forma, en el círculo unitario
+    pub fn sample(&self, rng: &mut Rng) -> (f32, f32) {
+        match self {
+            Bokeh::Circle => {
+                let radius = rng.next_f32().sqrt();
+                let angle = rng.next_f32() * 2.0 * PI;
+                (radius * angle.cos(), radius * angle.sin())
+            }
+            Bokeh::Polygon(fan) | Bokeh::Custom(fan) => fan.sample(rng),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Bokeh::Circle => "circle",
+            Bokeh::Polygon(_) => "polygon",
+            Bokeh::Custom(_) => "custom",
+        }
+    }
+}
+
+// Polígono estrellado dividido en triángulos desde el centro. Las áreas acumuladas se calculan
+// una sola vez para elegir cada triángulo según su área sin reservar memoria por muestra.
+#[derive(Clone)]
+pub struct Fan {
+    outline: Vec<(f32, f32)>,
+    cumulative_areas: Vec<f32>,
+}
+
+impl Fan {
+    fn new(outline: Vec<(f32, f32)>) -> Self {
+        let mut total = 0.0;
+        let cumulative_areas = (0..outline.len())
+            .map(|i| {
+                let (a, b) = (outline[i], outline[(i + 1) % outline.len()]);
+                total += (a.0 * b.1 - a.1 * b.0).abs() * 0.5;
+                total
+            })
+            .collect();
+        Fan { outline, cumulative_areas }
+    }
+
+    fn sample(&self, rng: &mut Rng) -> (f32, f32) {
+        let count = self.outline.len();
+        if count < 3 {
+            return (0.0, 0.0);
+        }
+
+        let pick = rng.next_f32() * self.cumulative_areas[count - 1];
+        let chosen = self.cumulative_areas.partition_point(|&area| area <= pick).min(count - 1);
+
+        let (a, b) = (self.outline[chosen], self.outline[(chosen + 1) % count]);
+        let (mut s, mut t) = (rng.next_f32(), rng.next_f32());
+        if s + t > 1.0 {
+            s = 1.0 - s;
+            t = 1.0 - t;
+        }
+        (a.0 * s + b.0 * t, a.1 * s + b.1 * t)
+    }
+}
+
+// Cómo se convierte cada píxel en un rayo primario
+#[derive(Clone, Copy)]
+pub enum Projection {
+    Perspective,
+    Orthographic { height: f32 },  // Alto visible en unidades del mundo
+    Fisheye { fov: f32 },          // Ángulo que cubre el círculo de la imagen (equidistante)
+    Equirectangular,               // Panorama de 360° x 180°, pensado para imágenes 2:1
+}
+
+impl Projection {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Projection::Perspective => "perspective",
+            Projection::Orthographic { .. } => "orthographic",
+            Projection::Fisheye { .. } => "fisheye",
+            Projection::Equirectangular => "equirectangular",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum CameraMode {
+    Orbit,    // Gira alrededor de `center`
+    FreeFly,  // Estilo primera persona: el ojo se mueve libremente y `center` lo sigue
+}
+
+impl CameraMode {
+    pub fn name(&self) -> &'static str {
+        match self {
+            CameraMode::Orbit => "orbit",
+            CameraMode::FreeFly => "free-fly",
+        }
+    }
+}
 
 pub struct Camera {
     pub eye: Vec3,
     pub center: Vec3,
-    pub up: Vec3
+    pub up: Vec3,
+    pub fov: f32,                   // Campo de visión vertical en radianes
+    pub aspect_ratio: Option<f32>,  // Relación de aspecto del sensor; `None` usa la de la imagen
+    pub zoom: f32,
+    pub projection: Projection,
+    pub mode: CameraMode,
+    pub move_speed: f32,  // Unidades por cuadro en modo libre
+    pub aperture: f32,        // Radio del lente; 0 es una cámara estenopeica sin desenfoque
+    pub focus_distance: f32,  // Distancia al plano enfocado, medida sobre la dirección de vista
+    pub bokeh: Bokeh,
+    pub shutter_open: f32,   // Apertura y cierre del obturador, en segundos respecto al
+    pub shutter_close: f32,  // instante del cuadro; si son iguales no hay desenfoque de movimiento
 }
 impl Camera {
     pub fn new(eye: Vec3, center: Vec3, up: Vec3) -> Self {
         Camera {
             eye,
             center,
-            up
+            up,
+            fov: DEFAULT_FOV,
+            aspect_ratio: None,
+            zoom: 1.0,
+            projection: Projection::Perspective,
+            mode: CameraMode::Orbit,
+            move_speed: 0.1,
+            aperture: 0.0,
+            focus_distance: (center - eye).magnitude(),
+            bokeh: Bokeh::Circle,
+            shutter_open: 0.0,
+            shutter_close: 0.0,
         }
     }
 
-    pub fn base_change(&self, vector: &Vec3) -> Vec3 {
+    pub fn zoom_by(&mut self, factor: f32) {
+        self.zoom = (self.zoom * factor).clamp(MIN_ZOOM, MAX_ZOOM);
+    }
+
+    // Campo de visión vertical de la perspectiva una vez aplicado el zoom
+    pub fn effective_fov(&self) -> f32 {
+        2.0 * ((self.fov * 0.5).tan() / self.zoom).atan()
+    }
+
+    // Lo que mide un píxel de una imagen de `image_height` de alto sobre un objeto a `distance`
+    // del ojo, cerca del centro de la imagen
+    pub fn pixel_size(&self, distance: f32, image_height: f32) -> f32 {
+        let view_height = match self.projection {
+            Projection::Perspective => 2.0 * distance * (self.fov * 0.5).tan(),
+            Projection::Orthographic { height } => height,
+            Projection::Fisheye { fov } => distance * fov,
+            Projection::Equirectangular => distance * PI,
+        };
+        view_height / self.zoom / image_height
+    }
+
+    // Rayo sin lente que pasa por el píxel (x, y) de una imagen width x height. Es `None`
+    // fuera del círculo de la proyección ojo de pez.
+    pub fn pinhole_ray(&self, x: f32, y: f32, width: f32, height: f32) -> Option<Ray> {
+        let aspect_ratio = self.aspect_ratio.unwrap_or(width / height);
+
+        let screen_x = ((2.0 * x) / width - 1.0) / self.zoom;
+        let screen_y = (-(2.0 * y) / height + 1.0) / self.zoom;
+
+        match self.projection {
+            Projection::Perspective => {
+                let perspective_scale = (self.fov * 0.5).tan();
+                let screen_x = screen_x * aspect_ratio * perspective_scale;
+                let screen_y = screen_y * perspective_scale;
+
+                Some(Ray::new(self.eye, self.base_change(&Vec3::new(screen_x, screen_y, -1.0))))
+            }
+            Projection::Orthographic { height } => {
+                let half_height = height * 0.5;
+                let (right, up, _) = self.basis();
+                let origin = self.eye + right * (screen_x * aspect_ratio * half_height) + up * (screen_y * half_height);
+
+                Some(Ray::new(origin, self.direction()))
+            }
+            Projection::Fisheye { fov } => {
+                let screen_x = screen_x * aspect_ratio;
+                let radius = (screen_x * screen_x + screen_y * screen_y).sqrt();
+                if radius > 1.0 {
+                    return None;
+                }
+
+                let theta = radius * fov * 0.5;
+                let phi = screen_y.atan2(screen_x);
+                let local = Vec3::new(theta.sin() * phi.cos(), theta.sin() * phi.sin(), -theta.cos());
+
+                Some(Ray::new(self.eye, self.base_change(&local)))
+            }
+            Projection::Equirectangular => {
+                let longitude = screen_x * PI;
+                let latitude = (screen_y * PI * 0.5).clamp(-PI * 0.5, PI * 0.5);
+                let local = Vec3::new(latitude.cos() * longitude.sin(), latitude.sin(), -latitude.cos() * longitude.cos());
+
+                Some(Ray::new(self.eye, self.base_change(&local)))
+            }
+        }
+    }
+
+    pub fn motion_blur(&self) -> bool {
+        self.shutter_close > self.shutter_open
+    }
+
+    // Rayo primario del cuadro en `time`. Con lente delgado el origen se toma sobre la
+    // apertura y todos los rayos de un píxel convergen en el plano de enfoque (solo en
+    // perspectiva); con el obturador abierto cada rayo lleva un instante distinto.
+    pub fn primary_ray(&self, x: f32, y: f32, width: f32, height: f32, time: f32, rng: &mut Rng) -> Option<Ray> {
+        let shutter = self.shutter_open + (self.shutter_close - self.shutter_open) * rng.next_f32();
+        let ray = self.pinhole_ray(x, y, width, height)?.with_time(time + shutter);
+        if self.aperture <= 0.0 || !matches!(self.projection, Projection::Perspective) {
+            return Some(ray);
+        }
+
+        let (right, up, forward) = self.basis();
+        let focus_point = ray.at(self.focus_distance / ray.direction.dot(&forward));
+        let (lens_x, lens_y) = self.bokeh.sample(rng);
+        let origin = self.eye + (right * lens_x + up * lens_y) * self.aperture;
+
+        Some(Ray::new(origin, (focus_point - origin).normalize()).with_time(ray.time))
+    }
+
+    // Enfoca el punto que está a `distance` en la dirección dada desde el ojo
+    pub fn focus_on(&mut self, direction: &Vec3, distance: f32) {
+        self.focus_distance = distance * direction.dot(&self.direction());
+    }
+
+    // Ejes derecha, arriba y adelante de la vista
+    pub fn basis(&self) -> (Vec3, Vec3, Vec3) {
         let forward = (self.center - self.eye).normalize();
         let right = forward.cross(&self.up).normalize();
         let up = right.cross(&forward).normalize();
+        (right, up, forward)
+    }
+
+    pub fn base_change(&self, vector: &Vec3) -> Vec3 {
+        let (right, up, forward) = self.basis();
 
         let rotated = vector.x * right + vector.y * up - vector.z * forward;
 
-        return rotated.normalize();
+        rotated.normalize()
     }
 
     pub fn orbit(&mut self, delta_yaw: f32, delta_pitch: f32) {
@@ -46,6 +295,36 @@ impl Camera {
         self.eye = new_eye;
     }
 
+    // Cambia entre órbita y vuelo libre sin mover la vista: en ambos modos `center`
+    // queda delante del ojo, así que al volver a órbita se gira alrededor de ese punto.
+    pub fn toggle_mode(&mut self) {
+        self.mode = match self.mode {
+            CameraMode::Orbit => CameraMode::FreeFly,
+            CameraMode::FreeFly => CameraMode::Orbit,
+        };
+    }
+
+    // Desplaza ojo y centro juntos en los ejes de la cámara (adelante, derecha, arriba)
+    pub fn fly(&mut self, forward: f32, right: f32, rise: f32) {
+        let (right_axis, _, forward_axis) = self.basis();
+        let offset = (forward_axis * forward + right_axis * right + self.up * rise) * self.move_speed;
+        self.eye += offset;
+        self.center += offset;
+    }
+
+    // Gira la dirección de vista alrededor del ojo, manteniendo la distancia al centro
+    pub fn look(&mut self, delta_yaw: f32, delta_pitch: f32) {
+        let view = self.center - self.eye;
+        let distance = view.magnitude();
+        let direction = view / distance;
+
+        let yaw = direction.z.atan2(direction.x) + delta_yaw;
+        let pitch = (direction.y.clamp(-1.0, 1.0).asin() + delta_pitch).clamp(-PI / 2.0 + 0.1, PI / 2.0 - 0.1);
+
+        let new_direction = Vec3::new(pitch.cos() * yaw.cos(), pitch.sin(), pitch.cos() * yaw.sin());
+        self.center = self.eye + new_direction * distance;
+    }
+
     // Este es el nuevo método que añadimos
     pub fn direction(&self) -> Vec3 {
         (self.center - self.eye).normalize()
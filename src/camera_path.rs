@@ -0,0 +1,225 @@
+use nalgebra_glm::Vec3;
+use std::fs;
+use crate::camera::{Camera, DEFAULT_FOV};
+
+const ARC_SAMPLES: usize = 256;  // Muestras de la tabla de longitud de arco
+
+// Punto de control de un recorrido de cámara
+#[derive(Clone, Copy)]
+pub struct CameraKey {
+    pub eye: Vec3,
+    pub center: Vec3,
+    pub fov: f32,
+}
+
+impl CameraKey {
+    pub fn from_camera(camera: &Camera) -> Self {
+        CameraKey {
+            eye: camera.eye,
+            center: camera.center,
+            fov: camera.fov,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Spline {
+    CatmullRom,  // Pasa por todos los puntos de control
+    Bezier,      // Una sola curva de Bézier; solo pasa por el primer y el último punto
+}
+
+pub struct CameraPath {
+    pub keys: Vec<CameraKey>,
+    pub spline: Spline,
+    pub look_at: Option<Vec3>,  // Si está definido, la cámara mira siempre a este punto
+    pub duration: f32,          // Segundos que dura el recorrido completo
+    arc_lengths: Vec<f32>,
+}
+
+fn catmull_rom<T>(p0: T, p1: T, p2: T, p3: T, t: f32) -> T
+where
+    T: Copy + std::ops::Add<Output = T> + std::ops::Sub<Output = T> + std::ops::Mul<f32, Output = T>,
+{
+    let t2 = t * t;
+    let t3 = t2 * t;
+    (p1 * 2.0 + (p2 - p0) * t + (p0 * 2.0 - p1 * 5.0 + p2 * 4.0 - p3) * t2 + (p1 * 3.0 - p0 - p2 * 3.0 + p3) * t3) * 0.5
+}
+
+fn bezier<T>(points: impl ExactSizeIterator<Item = T>, t: f32) -> T
+where
+    T: Copy + std::ops::Add<Output = T> + std::ops::Mul<f32, Output = T>,
+{
+    // Forma de Bernstein: suma cada punto con su peso, sin copiar los puntos de control
+    let degree = points.len() - 1;
+    let mut binomial = 1.0;
+    let mut weighted = points.enumerate().map(|(i, point)| {
+        let weight = binomial * t.powi(i as i32) * (1.0 - t).powi((degree - i) as i32);
+        binomial *= (degree - i) as f32 / (i + 1) as f32;
+        point * weight
+    });
+    let first = weighted.next().expect("una curva de Bézier necesita al menos un punto");
+    weighted.fold(first, |sum, point| sum + point)
+}
+
+fn parse_vec3(values: &[&str]) -> Result<Vec3, String> {
+    if values.len() != 3 {
+        return Err(format!("se esperaban tres números y hay {}", values.len()));
+    }
+    let mut parsed = [0.0; 3];
+    for (slot, value) in parsed.iter_mut().zip(values) {
+        *slot = value.parse().map_err(|_| format!("número inválido: {}", value))?;
+    }
+    Ok(Vec3::new(parsed[0], parsed[1], parsed[2]))
+}
+
+impl CameraPath {
+    pub fn new(spline: Spline, duration: f32) -> Self {
+        CameraPath {
+            keys: Vec::new(),
+            spline,
+            look_at: None,
+            duration,
+            arc_lengths: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, key: CameraKey) {
+        self.keys.push(key);
+        self.rebuild_arc_lengths();
+    }
+
+    // Evalúa la curva con su parámetro natural u en [0, 1]
+    fn evaluate(&self, u: f32) -> CameraKey {
+        let u = u.clamp(0.0, 1.0);
+        match self.keys.len() {
+            0 => CameraKey { eye: Vec3::zeros(), center: Vec3::new(0.0, 0.0, -1.0), fov: DEFAULT_FOV },
+            1 => self.keys[0],
+            count => match self.spline {
+                Spline::CatmullRom => {
+                    let segments = count - 1;
+                    let position = u * segments as f32;
+                    let segment = (position.floor() as usize).min(segments - 1);
+                    let t = position - segment as f32;
+
+                    let key = |i: isize| self.keys[i.clamp(0, count as isize - 1) as usize];
+                    let (k0, k1, k2, k3) = (key(segment as isize - 1), key(segment as isize), key(segment as isize + 1), key(segment as isize + 2));
+                    CameraKey {
+                        eye: catmull_rom(k0.eye, k1.eye, k2.eye, k3.eye, t),
+                        center: catmull_rom(k0.center, k1.center, k2.center, k3.center, t),
+                        fov: catmull_rom(k0.fov, k1.fov, k2.fov, k3.fov, t),
+                    }
+                }
+                Spline::Bezier => {
+                    CameraKey {
+                        eye: bezier(self.keys.iter().map(|key| key.eye), u),
+                        center: bezier(self.keys.iter().map(|key| key.center), u),
+                        fov: bezier(self.keys.iter().map(|key| key.fov), u),
+                    }
+                }
+            },
+        }
+    }
+
+    // Tabla acumulada de la longitud recorrida por el ojo, para avanzar a velocidad constante
+    fn rebuild_arc_lengths(&mut self) {
+        let mut lengths = Vec::with_capacity(ARC_SAMPLES + 1);
+        let mut total = 0.0;
+        let mut previous = self.evaluate(0.0).eye;
+        lengths.push(0.0);
+        for i in 1..=ARC_SAMPLES {
+            let eye = self.evaluate(i as f32 / ARC_SAMPLES as f32).eye;
+            total += (eye - previous).magnitude();
+            lengths.push(total);
+            previous = eye;
+        }
+        self.arc_lengths = lengths;
+    }
+
+    // Convierte la fracción de distancia recorrida s en el parámetro de la curva
+    fn reparameterize(&self, s: f32) -> f32 {
+        let total = *self.arc_lengths.last().unwrap_or(&0.0);
+        if total <= f32::EPSILON {
+            return s;
+        }
+
+        let target = s.clamp(0.0, 1.0) * total;
+        let index = self.arc_lengths.partition_point(|&length| length < target).clamp(1, ARC_SAMPLES);
+        let (before, after) = (self.arc_lengths[index - 1], self.arc_lengths[index]);
+        let fraction = if after > before { (target - before) / (after - before) } else { 0.0 };
+        (index as f32 - 1.0 + fraction) / ARC_SAMPLES as f32
+    }
+
+    // Estado de la cámara tras `time` segundos de recorrido; el recorrido se repite en bucle
+    pub fn sample(&self, time: f32) -> CameraKey {
+        let s = if self.duration > 0.0 { (time / self.duration).rem_euclid(1.0) } else { 0.0 };
+        let mut key = self.evaluate(self.reparameterize(s));
+        if let Some(target) = self.look_at {
+            key.center = target;
+        }
+        key
+    }
+
+    pub fn apply(&self, camera: &mut Camera, time: f32) {
+        let key = self.sample(time);
+        camera.eye = key.eye;
+        camera.center = key.center;
+        camera.fov = key.fov;
+    }
+
+    // Formato de texto, una instrucción por línea:
+    //   spline catmull-rom | bezier
+    //   duration <segundos>
+    //   look_at <x> <y> <z>
+    //   key <ojo x y z> <centro x y z> <fov en grados>
+    pub fn load(path: &str) -> Result<CameraPath, String> {
+        let text = fs::read_to_string(path).map_err(|error| format!("{}: {}", path, error))?;
+        let mut camera_path = CameraPath::new(Spline::CatmullRom, 10.0);
+
+        for (number, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            let words: Vec<&str> = line.split_whitespace().collect();
+            let error = |message: String| format!("{}:{}: {}", path, number + 1, message);
+
+            match words.as_slice() {
+                [] => {}
+                ["spline", "catmull-rom"] => camera_path.spline = Spline::CatmullRom,
+                ["spline", "bezier"] => camera_path.spline = Spline::Bezier,
+                ["duration", seconds] => {
+                    camera_path.duration = seconds.parse().map_err(|_| error(format!("duración inválida: {}", seconds)))?;
+                }
+                ["look_at", values @ ..] => camera_path.look_at = Some(parse_vec3(values).map_err(error)?),
+                ["key", values @ ..] if values.len() == 7 => {
+                    let fov: f32 = values[6].parse().map_err(|_| error(format!("fov inválido: {}", values[6])))?;
+                    camera_path.keys.push(CameraKey {
+                        eye: parse_vec3(&values[0..3]).map_err(error)?,
+                        center: parse_vec3(&values[3..6]).map_err(error)?,
+                        fov: fov.to_radians(),
+                    });
+                }
+                _ => return Err(error(format!("instrucción desconocida: {}", line))),
+            }
+        }
+
+        camera_path.rebuild_arc_lengths();
+        Ok(camera_path)
+    }
+
+    pub fn save(&self, path: &str) -> std::io::Result<()> {
+        let mut text = String::new();
+        text.push_str(match self.spline {
+            Spline::CatmullRom => "spline catmull-rom\n",
+            Spline::Bezier => "spline bezier\n",
+        });
+        text.push_str(&format!("duration {}\n", self.duration));
+        if let Some(target) = self.look_at {
+            text.push_str(&format!("look_at {} {} {}\n", target.x, target.y, target.z));
+        }
+        for key in &self.keys {
+            text.push_str(&format!(
+                "key {} {} {} {} {} {} {}\n",
+                key.eye.x, key.eye.y, key.eye.z, key.center.x, key.center.y, key.center.z, key.fov.to_degrees()
+            ));
+        }
+        fs::write(path, text)
+    }
+}
@@ -0,0 +1,151 @@
+use nalgebra_glm::{Vec3, dot};
+use std::f32::consts::PI;
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::ray_intersect::{Intersect, RayIntersect};
+use crate::stats::{self, Primitive};
+
+// Cápsula: un cilindro entre `start` y `end` cerrado con dos semiesferas.
+pub struct Capsule {
+    pub start: Vec3,
+    pub end: Vec3,
+    pub radius: f32,
+    pub material: Material,
+}
+
+#[derive(Clone, Copy)]
+enum Part {
+    Side,
+    StartCap,
+    EndCap,
+}
+
+fn sphere_span(origin: &Vec3, direction: &Vec3, center: &Vec3, radius: f32) -> Option<(f32, f32)> {
+    let oc = origin - center;
+    let a = dot(direction, direction);
+    let b = dot(&oc, direction);
+    let c = dot(&oc, &oc) - radius * radius;
+    let discriminant = b * b - a * c;
+    if discriminant < 0.0 {
+        return None;
+    }
+    let root = discriminant.sqrt();
+    Some(((-b - root) / a, (-b + root) / a))
+}
+
+impl Capsule {
+    fn axis(&self) -> (Vec3, f32) {
+        let axis = self.end - self.start;
+        let length = axis.magnitude();
+        if length > f32::EPSILON { (axis / length, length) } else { (Vec3::new(0.0, 1.0, 0.0), 0.0) }
+    }
+
+    // La cápsula es convexa: su intervalo es la envolvente de los de las dos esferas
+    // y el del cilindro central.
+    fn interval(&self, origin: &Vec3, direction: &Vec3) -> Option<((f32, Part), (f32, Part))> {
+        let mut enter = (f32::INFINITY, Part::Side);
+        let mut exit = (f32::NEG_INFINITY, Part::Side);
+
+        for (center, part) in [(self.start, Part::StartCap), (self.end, Part::EndCap)] {
+            if let Some((t0, t1)) = sphere_span(origin, direction, &center, self.radius) {
+                if t0 < enter.0 { enter = (t0, part); }
+                if t1 > exit.0 { exit = (t1, part); }
+            }
+        }
+
+        let (axis, length) = self.axis();
+        let oa = origin - self.start;
+        let along_origin = dot(&oa, &axis);
+        let along_direction = dot(direction, &axis);
+        let perp_origin = oa - axis * along_origin;
+        let perp_direction = direction - axis * along_direction;
+
+        let a = dot(&perp_direction, &perp_direction);
+        let b = dot(&perp_origin, &perp_direction);
+        let c = dot(&perp_origin, &perp_origin) - self.radius * self.radius;
+        let discriminant = b * b - a * c;
+        if a > f32::EPSILON && discriminant >= 0.0 {
+            let root = discriminant.sqrt();
+            for t in [(-b - root) / a, (-b + root) / a] {
+                let along = along_origin + t * along_direction;
+                if along > 0.0 && along < length {
+                    if t < enter.0 { enter = (t, Part::Side); }
+                    if t > exit.0 { exit = (t, Part::Side); }
+                }
+            }
+        }
+
+        if enter.0 > exit.0 {
+            return None;
+        }
+        Some((enter, exit))
+    }
+
+    fn hit_at(&self, origin: &Vec3, direction: &Vec3, (distance, part): (f32, Part)) -> Intersect<'_> {
+        let point = origin + direction * distance;
+        let (normal, (u, v)) = self.surface(&point, part);
+        Intersect::hit(distance, point, normal, (u, v), &self.material)
+    }
+
+    fn surface(&self, point: &Vec3, part: Part) -> (Vec3, (f32, f32)) {
+        let (axis, length) = self.axis();
+        let along = dot(&(point - self.start), &axis).clamp(0.0, length);
+        let normal = match part {
+            Part::StartCap => (point - self.start).normalize(),
+            Part::EndCap => (point - self.end).normalize(),
+            Part::Side => (point - (self.start + axis * along)).normalize(),
+        };
+
+        // Base ortonormal alrededor del eje para medir el ángulo de la coordenada u
+        let helper = if axis.x.abs() < 0.9 { Vec3::new(1.0, 0.0, 0.0) } else { Vec3::new(0.0, 1.0, 0.0) };
+        let tangent = axis.cross(&helper).normalize();
+        let bitangent = axis.cross(&tangent);
+        let u = 0.5 + dot(&normal, &bitangent).atan2(dot(&normal, &tangent)) / (2.0 * PI);
+
+        // v recorre la cápsula de punta a punta, incluyendo los casquetes
+        let cap_length = self.radius * PI * 0.5;
+        let arc = match part {
+            Part::StartCap => cap_length * (1.0 + dot(&normal, &axis).clamp(-1.0, 0.0)),
+            Part::Side => cap_length + along,
+            Part::EndCap => cap_length + length + cap_length * dot(&normal, &axis).clamp(0.0, 1.0),
+        };
+        (normal, (u, arc / (length + 2.0 * cap_length)))
+    }
+}
+
+impl RayIntersect for Capsule {
+    fn ray_intersect(&self, ray: &Ray) -> Intersect<'_> {
+        stats::record_test(Primitive::Capsule);
+        let (enter, exit) = match self.interval(&ray.origin, &ray.direction) {
+            Some(interval) => interval,
+            None => return Intersect::no_hit(),
+        };
+
+        let (distance, part) = if ray.contains(enter.0) {
+            enter
+        } else if ray.contains(exit.0) {
+            exit
+        } else {
+            return Intersect::no_hit();
+        };
+
+        self.hit_at(&ray.origin, &ray.direction, (distance, part))
+    }
+
+    fn intervals(&self, ray: &Ray) -> Vec<(Intersect<'_>, Intersect<'_>)> {
+        stats::record_test(Primitive::Capsule);
+        match self.interval(&ray.origin, &ray.direction) {
+            Some((enter, exit)) => vec![(
+                self.hit_at(&ray.origin, &ray.direction, enter),
+                self.hit_at(&ray.origin, &ray.direction, exit),
+            )],
+            None => Vec::new(),
+        }
+    }
+
+    fn occluded(&self, ray: &Ray) -> bool {
+        stats::record_test(Primitive::Capsule);
+        self.interval(&ray.origin, &ray.direction)
+            .is_some_and(|(enter, exit)| ray.contains(enter.0) || ray.contains(exit.0))
+    }
+}
@@ -0,0 +1,304 @@
+use std::fmt::Display;
+use std::str::FromStr;
+use crate::camera::DEFAULT_FOV;
+use crate::render_mode::{RenderMode, RENDER_MODES};
+use crate::settings::{
+    RenderSettings, Termination, BLUR_SAMPLES, DAY_DURATION, DEFAULT_MAX_DEPTH, DEFAULT_MIN_CONTRIBUTION,
+    DEFAULT_REFLECTION_DEPTH, DEFAULT_REFRACTION_DEPTH, DEFAULT_FOG_STEPS,
+    DEFAULT_SUBSURFACE_SAMPLES,
+};
+use crate::stats::MAX_TRACKED_DEPTH;
+
+const DEFAULT_FPS: f32 = 30.0;  // Cuadros por segundo de animate
+const DEFAULT_RESOLUTION: (usize, usize) = (800, 600);
+const DEFAULT_IMAGE: &str = "render.ppm";
+const DEFAULT_FRAMES: &str = "cuadros";
+const MAX_RESOLUTION: usize = 16384;
+const MAX_SAMPLES: u32 = 4096;
+const MAX_FOG_STEPS: u32 = 256;
+const MAX_SUBSURFACE_SAMPLES: u32 = 256;
+const SUNRISE_HOUR: f32 = 6.0;  // Hora del reloj en el instante 0 del ciclo
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Command {
+    View,     // Ventana interactiva
+    Render,   // Una imagen sin ventana
+    Animate,  // Una secuencia de cuadros con el reloj virtual
+}
+
+const COMMANDS: [Command; 3] = [Command::View, Command::Render, Command::Animate];
+
+const COMMON_OPTIONS: [&str; 18] = [
+    "--scene", "--path", "--resolution", "--fov", "--aspect", "--samples", "--max-depth", "--reflection-depth", "--refraction-depth",
+    "--min-contribution", "--roulette", "--terminate", "--fog-steps", "--subsurface-samples", "--mode", "--start", "--day-length", "--seed",
+];
+
+impl Command {
+    pub fn name(self) -> &'static str {
+        match self {
+            Command::View => "view",
+            Command::Render => "render",
+            Command::Animate => "animate",
+        }
+    }
+
+    // Opciones propias del subcomando, además de las comunes
+    fn options(self) -> &'static [&'static str] {
+        match self {
+            Command::View => &[],
+            Command::Render => &["--output", "--stats"],
+            Command::Animate => &["--output", "--stats", "--fps", "--duration"],
+        }
+    }
+
+    fn accepts(self, option: &str) -> bool {
+        COMMON_OPTIONS.contains(&option) || self.options().contains(&option)
+    }
+}
+
+pub struct Options {
+    pub command: Command,
+    pub width: usize,
+    pub height: usize,
+    pub settings: RenderSettings,
+    pub fov: Option<f32>,           // Radianes; sin valor, el de la escena o el de la cámara
+    pub aspect_ratio: Option<f32>,  // Sin valor, el de la escena o el de la imagen
+    pub scene: Option<String>,
+    pub path: Option<String>,
+    pub start: f32,  // Segundos desde el amanecer
+    pub output: String,
+    pub fps: f32,
+    pub duration: Option<f32>,  // Sin valor: la del recorrido indicado con --path o un día completo
+    pub stats: Option<String>,
+}
+
+pub fn help() -> String {
+    let modes: Vec<&str> = RENDER_MODES.iter().map(|mode| mode.name()).collect();
+    format!(
+        "Uso: raytracing_project [view | render | animate] [opciones]
+
+Subcomandos:
+  view                       Abre la ventana interactiva (por defecto)
+  render                     Renderiza una sola imagen sin abrir la ventana
+  animate                    Renderiza una secuencia de cuadros con un reloj virtual
+
+Opciones comunes:
+  --scene <archivo>          Escena a cargar (por defecto scene.txt)
+  --path <archivo>           Recorrido de cámara (por defecto camera_path.txt)
+  --resolution <ancho>x<alto>  Tamaño de la imagen (por defecto {}x{})
+  --fov <grados>             Campo de visión vertical; reemplaza al de la línea camera de la escena
+                             (por defecto {})
+  --aspect <relación>        Aspecto del sensor, como 1.78 o 16:9 (por defecto el de la imagen)
+  --samples <n>              Muestras por píxel, hasta {}; con más de una se suavizan los bordes
+                             (por defecto 1, o {} con desenfoque de lente o de movimiento)
+  --max-depth <n>            Rebotes de reflexión y refracción en total, de 0 a {} (por defecto {})
+  --reflection-depth <n>     Rebotes de reflexión (por defecto {})
+  --refraction-depth <n>     Rebotes de refracción (por defecto {})
+  --min-contribution <peso>  Corta los rayos que pesan menos que esto en el píxel, entre 0 y 1
+                             (por defecto {:.4})
+  --roulette <n>             Aplica ruleta rusa desde el rebote n; más rápido pero con ruido
+  --terminate <corte>        Lo que aporta un rayo cortado: black o ambient (por defecto ambient)
+  --fog-steps <n>            Pasos por rayo de los haces de luz en la niebla, hasta {}; con 0 la niebla
+                             solo atenúa, con 16 ya se ven los haces (por defecto {})
+  --subsurface-samples <n>   Puntos por impacto en los materiales translúcidos, hasta {}; con 0 se
+                             sombrean sin dispersión (por defecto {})
+  --mode <vista>             {} (por defecto shaded)
+  --start <hora>             Momento del día al empezar: HH:MM, con el amanecer a las 06:00,
+                             o segundos desde el amanecer (por defecto 06:00)
+  --day-length <segundos>    Duración del ciclo de día y noche (por defecto {})
+  --seed <n>                 Semilla del muestreo aleatorio (por defecto 0)
+
+render:
+  --output <archivo.ppm>     Imagen de salida (por defecto {})
+  --stats <archivo.json>     Guarda las estadísticas del render en JSON
+
+animate:
+  --output <destino>         Carpeta de imágenes PPM o archivo .gif, .y4m o .rgb (por defecto {})
+  --fps <n>                  Cuadros por segundo (por defecto {})
+  --duration <segundos>      Duración; por defecto la del recorrido indicado con --path o un día
+  --stats <archivo.json>     Guarda las estadísticas del render en JSON
+
+  -h, --help                 Muestra esta ayuda",
+        DEFAULT_RESOLUTION.0, DEFAULT_RESOLUTION.1, DEFAULT_FOV.to_degrees(), MAX_SAMPLES, BLUR_SAMPLES, MAX_TRACKED_DEPTH,
+        DEFAULT_MAX_DEPTH, DEFAULT_REFLECTION_DEPTH, DEFAULT_REFRACTION_DEPTH, DEFAULT_MIN_CONTRIBUTION,
+        MAX_FOG_STEPS, DEFAULT_FOG_STEPS, MAX_SUBSURFACE_SAMPLES, DEFAULT_SUBSURFACE_SAMPLES, modes.join(", "), DAY_DURATION, DEFAULT_IMAGE, DEFAULT_FRAMES, DEFAULT_FPS,
+    )
+}
+
+fn number<T: FromStr>(option: &str, value: &str) -> Result<T, String> {
+    value.parse().map_err(|_| format!("valor inválido para {}: {}", option, value))
+}
+
+fn in_range<T: FromStr + PartialOrd + Display>(option: &str, value: &str, min: T, max: T) -> Result<T, String> {
+    let parsed: T = number(option, value)?;
+    // Con `contains` un NaN queda afuera, porque toda comparación con él es falsa
+    if !(&min..=&max).contains(&&parsed) {
+        return Err(format!("{} debe estar entre {} y {}", option, min, max));
+    }
+    Ok(parsed)
+}
+
+fn positive(option: &str, value: &str) -> Result<f32, String> {
+    let parsed: f32 = number(option, value)?;
+    if !parsed.is_finite() || parsed <= 0.0 {
+        return Err(format!("{} debe ser mayor que cero", option));
+    }
+    Ok(parsed)
+}
+
+// Relación de aspecto como número (1.78) o como ancho:alto (16:9)
+fn aspect_ratio(value: &str) -> Result<f32, String> {
+    match value.split_once(':') {
+        Some((width, height)) => Ok(positive("el ancho de --aspect", width)? / positive("el alto de --aspect", height)?),
+        None => positive("--aspect", value),
+    }
+}
+
+fn resolution(value: &str) -> Result<(usize, usize), String> {
+    let (width, height) = value.split_once('x')
+        .ok_or_else(|| format!("resolución inválida: {} (se espera <ancho>x<alto>, p. ej. 800x600)", value))?;
+    Ok((
+        in_range("el ancho", width, 1, MAX_RESOLUTION)?,
+        in_range("el alto", height, 1, MAX_RESOLUTION)?,
+    ))
+}
+
+// Segundos desde el amanecer para una hora HH:MM o un número de segundos
+fn start_time(value: &str, day_duration: f32) -> Result<f32, String> {
+    match value.split_once(':') {
+        Some((hours, minutes)) => {
+            let hours: f32 = in_range("la hora de --start", hours, 0.0, 23.0)?;
+            let minutes: f32 = in_range("los minutos de --start", minutes, 0.0, 59.0)?;
+            let since_sunrise = (hours + minutes / 60.0 - SUNRISE_HOUR).rem_euclid(24.0);
+            Ok(since_sunrise / 24.0 * day_duration)
+        }
+        None => {
+            let seconds: f32 = number("--start", value)?;
+            if !seconds.is_finite() {
+                return Err(format!("valor inválido para --start: {}", value));
+            }
+            Ok(seconds)
+        }
+    }
+}
+
+// Lee los argumentos del programa (sin el nombre del ejecutable). Devuelve `None` si se pidió
+// la ayuda.
+pub fn parse(args: &[String]) -> Result<Option<Options>, String> {
+    let mut rest = args.iter().map(String::as_str).peekable();
+
+    let command = match rest.peek() {
+        Some(&"help") => return Ok(None),
+        Some(word) if !word.starts_with('-') => {
+            let command = COMMANDS.iter()
+                .find(|command| command.name() == *word)
+                .copied()
+                .ok_or_else(|| format!("subcomando desconocido: {} (disponibles: view, render, animate)", word))?;
+            rest.next();
+            command
+        }
+        _ => Command::View,
+    };
+
+    let mut options = Options {
+        command,
+        width: DEFAULT_RESOLUTION.0,
+        height: DEFAULT_RESOLUTION.1,
+        settings: RenderSettings::default(),
+        fov: None,
+        aspect_ratio: None,
+        scene: None,
+        path: None,
+        start: 0.0,
+        output: if command == Command::Animate { DEFAULT_FRAMES } else { DEFAULT_IMAGE }.to_string(),
+        fps: DEFAULT_FPS,
+        duration: None,
+        stats: None,
+    };
+    let mut start = None;
+
+    while let Some(option) = rest.next() {
+        if option == "-h" || option == "--help" {
+            return Ok(None);
+        }
+        if !command.accepts(option) {
+            let others: Vec<&str> = COMMANDS.iter().filter(|other| other.accepts(option)).map(|other| other.name()).collect();
+            return Err(if others.is_empty() {
+                format!("opción desconocida: {}", option)
+            } else {
+                format!("{} solo se puede usar con {}", option, others.join(" o "))
+            });
+        }
+        let value = rest.next().ok_or_else(|| format!("falta el valor de {}", option))?;
+
+        match option {
+            "--scene" => options.scene = Some(value.to_string()),
+            "--path" => options.path = Some(value.to_string()),
+            "--resolution" => (options.width, options.height) = resolution(value)?,
+            "--fov" => options.fov = Some(in_range(option, value, 1.0f32, 179.0)?.to_radians()),
+            "--aspect" => options.aspect_ratio = Some(aspect_ratio(value)?),
+            "--samples" => options.settings.samples = Some(in_range(option, value, 1, MAX_SAMPLES)?),
+            "--max-depth" => options.settings.max_depth = in_range(option, value, 0, MAX_TRACKED_DEPTH as u32)?,
+            "--reflection-depth" => options.settings.max_reflection_depth = in_range(option, value, 0, MAX_TRACKED_DEPTH as u32)?,
+            "--refraction-depth" => options.settings.max_refraction_depth = in_range(option, value, 0, MAX_TRACKED_DEPTH as u32)?,
+            "--min-contribution" => options.settings.min_contribution = in_range(option, value, 0.0, 1.0)?,
+            "--roulette" => options.settings.roulette_depth = Some(in_range(option, value, 0, MAX_TRACKED_DEPTH as u32)?),
+            "--terminate" => options.settings.termination = Termination::from_name(value)?,
+            "--fog-steps" => options.settings.fog_steps = in_range(option, value, 0, MAX_FOG_STEPS)?,
+            "--subsurface-samples" => options.settings.subsurface_samples = in_range(option, value, 0, MAX_SUBSURFACE_SAMPLES)?,
+            "--mode" => options.settings.mode = RenderMode::from_name(value)?,
+            "--start" => start = Some(value),
+            "--day-length" => options.settings.day_duration = positive(option, value)?,
+            "--seed" => options.settings.seed = number(option, value)?,
+            "--output" => options.output = value.to_string(),
+            "--stats" => options.stats = Some(value.to_string()),
+            "--fps" => options.fps = positive(option, value)?,
+            "--duration" => options.duration = Some(positive(option, value)?),
+            _ => unreachable!("opción aceptada sin tratar: {}", option),
+        }
+    }
+
+    // La hora se convierte al final porque depende de --day-length
+    if let Some(start) = start {
+        options.start = start_time(start, options.settings.day_duration)?;
+    }
+    if command == Command::Render && !options.output.to_ascii_lowercase().ends_with(".ppm") {
+        return Err(format!("render escribe imágenes PPM; la salida debe terminar en .ppm: {}", options.output));
+    }
+
+    Ok(Some(options))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_args(args: &[&str]) -> Result<Option<Options>, String> {
+        parse(&args.iter().map(|arg| arg.to_string()).collect::<Vec<String>>())
+    }
+
+    fn parse_error(args: &[&str]) -> String {
+        match parse_args(args) {
+            Ok(_) => panic!("{:?} debería dar un error", args),
+            Err(error) => error,
+        }
+    }
+
+    #[test]
+    fn ranges_reject_nan() {
+        assert_eq!(parse_error(&["render", "--fov", "nan"]), "--fov debe estar entre 1 y 179");
+        assert_eq!(parse_error(&["render", "--min-contribution", "NaN"]), "--min-contribution debe estar entre 0 y 1");
+        assert_eq!(parse_error(&["render", "--start", "nan:00"]), "la hora de --start debe estar entre 0 y 23");
+    }
+
+    #[test]
+    fn ranges_reject_values_outside_and_accept_the_limits() {
+        assert_eq!(parse_error(&["render", "--fov", "180"]), "--fov debe estar entre 1 y 179");
+        assert_eq!(parse_error(&["render", "--min-contribution", "-0.1"]), "--min-contribution debe estar entre 0 y 1");
+        assert_eq!(parse_error(&["render", "--fov", "inf"]), "--fov debe estar entre 1 y 179");
+
+        let options = parse_args(&["render", "--fov", "179", "--min-contribution", "1"]).unwrap().unwrap();
+        assert!((options.fov.unwrap() - 179f32.to_radians()).abs() < 1e-6);
+        assert_eq!(options.settings.min_contribution, 1.0);
+    }
+}
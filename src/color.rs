@@ -23,9 +23,22 @@ impl Color {
         Color { r: 0, g: 0, b: 0 }
     }
 
-    pub fn to_hex(&self) -> u32 {
+    pub fn to_hex(self) -> u32 {
         ((self.r as u32) << 16) | ((self.g as u32) << 8) | (self.b as u32)
     }
+
+    // Componentes en punto flotante, útiles para promediar varias muestras sin saturar
+    pub fn to_rgb(self) -> [f32; 3] {
+        [self.r as f32, self.g as f32, self.b as f32]
+    }
+
+    pub fn from_rgb(rgb: [f32; 3]) -> Self {
+        Color {
+            r: rgb[0].round().clamp(0.0, 255.0) as u8,
+            g: rgb[1].round().clamp(0.0, 255.0) as u8,
+            b: rgb[2].round().clamp(0.0, 255.0) as u8,
+        }
+    }
 }
 
 use std::ops::Add;
@@ -0,0 +1,154 @@
+use nalgebra_glm::Vec3;
+use std::f32::consts::PI;
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::ray_intersect::{Intersect, RayIntersect};
+use crate::stats::{self, Primitive};
+
+// Cono con tapa, alineado con el eje Y. `base` es el centro de la tapa y el vértice
+// queda en `base + (0, height, 0)`.
+pub struct Cone {
+    pub base: Vec3,
+    pub radius: f32,
+    pub height: f32,
+    pub material: Material,
+}
+
+#[derive(Clone, Copy)]
+enum Part {
+    Side,
+    Bottom,
+}
+
+impl Cone {
+    fn slope(&self) -> f32 {
+        self.radius / self.height
+    }
+
+    fn interval(&self, origin: &Vec3, direction: &Vec3) -> Option<((f32, Part), (f32, Part))> {
+        // Entre las tapas (0 <= y <= height) el interior del cono doble es convexo,
+        // así que basta con recortar sus tramos contra esa franja.
+        let (slab_enter, slab_exit) = if direction.y.abs() < f32::EPSILON {
+            if origin.y < 0.0 || origin.y > self.height {
+                return None;
+            }
+            ((f32::NEG_INFINITY, Part::Bottom), (f32::INFINITY, Part::Bottom))
+        } else {
+            let bottom = (-origin.y / direction.y, Part::Bottom);
+            let apex = ((self.height - origin.y) / direction.y, Part::Side);
+            if bottom.0 < apex.0 { (bottom, apex) } else { (apex, bottom) }
+        };
+
+        let k2 = self.slope() * self.slope();
+        let h = self.height - origin.y;
+        let a = direction.x * direction.x + direction.z * direction.z - k2 * direction.y * direction.y;
+        let b = origin.x * direction.x + origin.z * direction.z + k2 * h * direction.y;
+        let c = origin.x * origin.x + origin.z * origin.z - k2 * h * h;
+
+        // Tramos del rayo donde a*t^2 + 2*b*t + c <= 0
+        let spans: [(f32, f32); 2] = if a.abs() < f32::EPSILON {
+            if b.abs() < f32::EPSILON {
+                if c > 0.0 { return None; }
+                [(f32::NEG_INFINITY, f32::INFINITY), (1.0, 0.0)]
+            } else {
+                let t = -c / (2.0 * b);
+                if b > 0.0 {
+                    [(f32::NEG_INFINITY, t), (1.0, 0.0)]
+                } else {
+                    [(t, f32::INFINITY), (1.0, 0.0)]
+                }
+            }
+        } else {
+            let discriminant = b * b - a * c;
+            if discriminant < 0.0 {
+                if a > 0.0 { return None; }
+                [(f32::NEG_INFINITY, f32::INFINITY), (1.0, 0.0)]
+            } else {
+                let root = discriminant.sqrt();
+                let t0 = ((-b - root) / a).min((-b + root) / a);
+                let t1 = ((-b - root) / a).max((-b + root) / a);
+                if a > 0.0 {
+                    [(t0, t1), (1.0, 0.0)]
+                } else {
+                    [(f32::NEG_INFINITY, t0), (t1, f32::INFINITY)]
+                }
+            }
+        };
+
+        spans.iter()
+            .filter(|(start, end)| start <= end)
+            .map(|&(start, end)| {
+                let enter = if start > slab_enter.0 { (start, Part::Side) } else { slab_enter };
+                let exit = if end < slab_exit.0 { (end, Part::Side) } else { slab_exit };
+                (enter, exit)
+            })
+            .filter(|(enter, exit)| enter.0 < exit.0)
+            .max_by(|x, y| (x.1 .0 - x.0 .0).total_cmp(&(y.1 .0 - y.0 .0)))
+    }
+
+    fn hit_at(&self, local_origin: &Vec3, direction: &Vec3, (distance, part): (f32, Part)) -> Intersect<'_> {
+        let local = local_origin + direction * distance;
+        let (normal, (u, v)) = self.surface(&local, part);
+        Intersect::hit(distance, local + self.base, normal, (u, v), &self.material)
+    }
+
+    fn surface(&self, local: &Vec3, part: Part) -> (Vec3, (f32, f32)) {
+        match part {
+            Part::Side => {
+                let radial = (local.x * local.x + local.z * local.z).sqrt();
+                let normal = if radial > f32::EPSILON {
+                    Vec3::new(local.x, self.slope() * radial, local.z).normalize()
+                } else {
+                    Vec3::new(0.0, 1.0, 0.0)
+                };
+                let u = 0.5 + local.z.atan2(local.x) / (2.0 * PI);
+                (normal, (u, local.y / self.height))
+            }
+            Part::Bottom => {
+                let u = 0.5 + local.x / (2.0 * self.radius);
+                let v = 0.5 + local.z / (2.0 * self.radius);
+                (Vec3::new(0.0, -1.0, 0.0), (u, v))
+            }
+        }
+    }
+}
+
+impl RayIntersect for Cone {
+    fn ray_intersect(&self, ray: &Ray) -> Intersect<'_> {
+        stats::record_test(Primitive::Cone);
+        let local_origin = ray.origin - self.base;
+        let (enter, exit) = match self.interval(&local_origin, &ray.direction) {
+            Some(interval) => interval,
+            None => return Intersect::no_hit(),
+        };
+
+        let (distance, part) = if ray.contains(enter.0) {
+            enter
+        } else if ray.contains(exit.0) {
+            exit
+        } else {
+            return Intersect::no_hit();
+        };
+
+        self.hit_at(&local_origin, &ray.direction, (distance, part))
+    }
+
+    fn intervals(&self, ray: &Ray) -> Vec<(Intersect<'_>, Intersect<'_>)> {
+        stats::record_test(Primitive::Cone);
+        let local_origin = ray.origin - self.base;
+        match self.interval(&local_origin, &ray.direction) {
+            Some((enter, exit)) => vec![(
+                self.hit_at(&local_origin, &ray.direction, enter),
+                self.hit_at(&local_origin, &ray.direction, exit),
+            )],
+            None => Vec::new(),
+        }
+    }
+
+    fn occluded(&self, ray: &Ray) -> bool {
+        stats::record_test(Primitive::Cone);
+        let local_origin = ray.origin - self.base;
+        self.interval(&local_origin, &ray.direction)
+            .is_some_and(|(enter, exit)| ray.contains(enter.0) || ray.contains(exit.0))
+    }
+}
@@ -0,0 +1,87 @@
+use crate::ray::Ray;
+use crate::ray_intersect::{Intersect, RayIntersect};
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum CsgOp {
+    Union,
+    Intersection,
+    Difference,
+}
+
+impl CsgOp {
+    fn inside(self, in_left: bool, in_right: bool) -> bool {
+        match self {
+            CsgOp::Union => in_left || in_right,
+            CsgOp::Intersection => in_left && in_right,
+            CsgOp::Difference => in_left && !in_right,
+        }
+    }
+}
+
+// Geometría constructiva: combina dos sólidos cualesquiera. Cada superficie del
+// resultado conserva el material del operando que la generó, así que el corte de
+// una diferencia muestra el material del sólido que se restó.
+pub struct Csg {
+    pub op: CsgOp,
+    pub left: Box<dyn RayIntersect>,
+    pub right: Box<dyn RayIntersect>,
+}
+
+impl Csg {
+    pub fn new(op: CsgOp, left: Box<dyn RayIntersect>, right: Box<dyn RayIntersect>) -> Self {
+        Csg { op, left, right }
+    }
+}
+
+impl RayIntersect for Csg {
+    fn ray_intersect(&self, ray: &Ray) -> Intersect<'_> {
+        for (enter, exit) in self.intervals(ray) {
+            if ray.contains(enter.distance) {
+                return enter;
+            }
+            if ray.contains(exit.distance) {
+                return exit;
+            }
+        }
+        Intersect::no_hit()
+    }
+
+    fn intervals(&self, ray: &Ray) -> Vec<(Intersect<'_>, Intersect<'_>)> {
+        // Recorremos los bordes de ambos operandos en orden y registramos cada vez
+        // que el rayo entra o sale del resultado de la operación.
+        let mut events = Vec::new();
+        for (is_left, object) in [(true, &self.left), (false, &self.right)] {
+            for (enter, exit) in object.intervals(ray) {
+                events.push((enter, is_left, true));
+                events.push((exit, is_left, false));
+            }
+        }
+        events.sort_by(|a, b| a.0.distance.total_cmp(&b.0.distance));
+
+        let mut spans = Vec::new();
+        let mut current_enter = None;
+        let (mut in_left, mut in_right) = (false, false);
+
+        for (mut boundary, is_left, entering) in events {
+            let was_inside = self.op.inside(in_left, in_right);
+            if is_left { in_left = entering; } else { in_right = entering; }
+            let now_inside = self.op.inside(in_left, in_right);
+            if was_inside == now_inside {
+                continue;
+            }
+
+            // En una diferencia las caras del sólido restado quedan hacia adentro
+            if !is_left && self.op == CsgOp::Difference {
+                boundary.normal = -boundary.normal;
+            }
+
+            if now_inside {
+                current_enter = Some(boundary);
+            } else if let Some(enter) = current_enter.take() {
+                spans.push((enter, boundary));
+            }
+        }
+
+        spans
+    }
+}
@@ -1,6 +1,8 @@
-use nalgebra_glm::{Vec3, dot};
+use nalgebra_glm::Vec3;
 use crate::material::Material;
-use crate::ray_intersect::{Intersect, RayIntersect};
+use crate::ray::Ray;
+use crate::ray_intersect::{spherical_uv, Intersect, RayIntersect};
+use crate::stats::{self, Primitive};
 
 pub struct Cube {
     pub min: Vec3,
@@ -8,41 +10,57 @@ pub struct Cube {
     pub material: Material,
 }
 
-impl RayIntersect for Cube {
-    fn ray_intersect(&self, origin: &Vec3, direction: &Vec3) -> Intersect {
-        let inv_dir = Vec3::new(1.0 / direction.x, 1.0 / direction.y, 1.0 / direction.z);
-        
-        let mut tmin = (self.min.x - origin.x) * inv_dir.x;
-        let mut tmax = (self.max.x - origin.x) * inv_dir.x;
-        
-        if tmin > tmax { std::mem::swap(&mut tmin, &mut tmax); }
+impl Cube {
+    // Método de las franjas: intervalo de la recta del rayo dentro de la caja
+    fn slabs(&self, ray: &Ray) -> Option<(f32, f32)> {
+        let mut tmin = f32::NEG_INFINITY;
+        let mut tmax = f32::INFINITY;
 
-        let mut tymin = (self.min.y - origin.y) * inv_dir.y;
-        let mut tymax = (self.max.y - origin.y) * inv_dir.y;
+        for axis in 0..3 {
+            let inv_dir = 1.0 / ray.direction[axis];
+            let mut near = (self.min[axis] - ray.origin[axis]) * inv_dir;
+            let mut far = (self.max[axis] - ray.origin[axis]) * inv_dir;
 
-        if tymin > tymax { std::mem::swap(&mut tymin, &mut tymax); }
+            if near > far { std::mem::swap(&mut near, &mut far); }
 
-        if (tmin > tymax) || (tymin > tmax) {
-            return Intersect::no_hit();
+            tmin = tmin.max(near);
+            tmax = tmax.min(far);
+            if tmin > tmax {
+                return None;
+            }
         }
 
-        if tymin > tmin { tmin = tymin; }
-        if tymax < tmax { tmax = tymax; }
+        Some((tmin, tmax))
+    }
 
-        let mut tzmin = (self.min.z - origin.z) * inv_dir.z;
-        let mut tzmax = (self.max.z - origin.z) * inv_dir.z;
+    fn hit_at(&self, ray: &Ray, distance: f32) -> Intersect<'_> {
+        let hit_point = ray.at(distance);
+        let normal = (hit_point - (self.max + self.min) * 0.5).normalize();
 
-        if tzmin > tzmax { std::mem::swap(&mut tzmin, &mut tzmax); }
+        Intersect::hit(distance, hit_point, normal, spherical_uv(&normal), &self.material)
+    }
+}
 
-        if (tmin > tzmax) || (tzmin > tmax) {
-            return Intersect::no_hit();
+impl RayIntersect for Cube {
+    fn ray_intersect(&self, ray: &Ray) -> Intersect<'_> {
+        stats::record_test(Primitive::Cube);
+        match self.slabs(ray) {
+            Some((tmin, _)) if ray.contains(tmin) => self.hit_at(ray, tmin),
+            Some((_, tmax)) if ray.contains(tmax) => self.hit_at(ray, tmax),
+            _ => Intersect::no_hit(),
         }
+    }
 
-        let distance = if tzmin > tmin { tzmin } else { tmin };
-
-        let hit_point = origin + direction * distance;
-        let normal = (hit_point - (self.max + self.min) * 0.5).normalize();
+    fn intervals(&self, ray: &Ray) -> Vec<(Intersect<'_>, Intersect<'_>)> {
+        stats::record_test(Primitive::Cube);
+        match self.slabs(ray) {
+            Some((tmin, tmax)) => vec![(self.hit_at(ray, tmin), self.hit_at(ray, tmax))],
+            None => Vec::new(),
+        }
+    }
 
-        Intersect::hit(distance, hit_point, normal, &self.material)
+    fn occluded(&self, ray: &Ray) -> bool {
+        stats::record_test(Primitive::Cube);
+        self.slabs(ray).is_some_and(|(tmin, tmax)| ray.contains(tmin) || ray.contains(tmax))
     }
 }
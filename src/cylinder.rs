@@ -0,0 +1,128 @@
+use nalgebra_glm::Vec3;
+use std::f32::consts::PI;
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::ray_intersect::{Intersect, RayIntersect};
+use crate::stats::{self, Primitive};
+
+// Cilindro con tapas, alineado con el eje Y. `base` es el centro de la tapa inferior.
+pub struct Cylinder {
+    pub base: Vec3,
+    pub radius: f32,
+    pub height: f32,
+    pub material: Material,
+}
+
+#[derive(Clone, Copy)]
+enum Part {
+    Side,
+    Bottom,
+    Top,
+}
+
+impl Cylinder {
+    // Intervalo [entrada, salida] del rayo dentro del sólido, en coordenadas locales
+    fn interval(&self, origin: &Vec3, direction: &Vec3) -> Option<((f32, Part), (f32, Part))> {
+        let mut enter = (f32::NEG_INFINITY, Part::Side);
+        let mut exit = (f32::INFINITY, Part::Side);
+
+        let a = direction.x * direction.x + direction.z * direction.z;
+        let b = origin.x * direction.x + origin.z * direction.z;
+        let c = origin.x * origin.x + origin.z * origin.z - self.radius * self.radius;
+
+        if a.abs() < f32::EPSILON {
+            if c > 0.0 {
+                return None;
+            }
+        } else {
+            let discriminant = b * b - a * c;
+            if discriminant < 0.0 {
+                return None;
+            }
+            let root = discriminant.sqrt();
+            enter.0 = (-b - root) / a;
+            exit.0 = (-b + root) / a;
+        }
+
+        if direction.y.abs() < f32::EPSILON {
+            if origin.y < 0.0 || origin.y > self.height {
+                return None;
+            }
+        } else {
+            let mut near = (-origin.y / direction.y, Part::Bottom);
+            let mut far = ((self.height - origin.y) / direction.y, Part::Top);
+            if near.0 > far.0 {
+                std::mem::swap(&mut near, &mut far);
+            }
+            if near.0 > enter.0 { enter = near; }
+            if far.0 < exit.0 { exit = far; }
+        }
+
+        if enter.0 > exit.0 {
+            return None;
+        }
+        Some((enter, exit))
+    }
+
+    fn hit_at(&self, local_origin: &Vec3, direction: &Vec3, (distance, part): (f32, Part)) -> Intersect<'_> {
+        let local = local_origin + direction * distance;
+        let (normal, (u, v)) = self.surface(&local, part);
+        Intersect::hit(distance, local + self.base, normal, (u, v), &self.material)
+    }
+
+    fn surface(&self, local: &Vec3, part: Part) -> (Vec3, (f32, f32)) {
+        match part {
+            Part::Side => {
+                let normal = Vec3::new(local.x, 0.0, local.z).normalize();
+                let u = 0.5 + local.z.atan2(local.x) / (2.0 * PI);
+                (normal, (u, local.y / self.height))
+            }
+            Part::Bottom | Part::Top => {
+                let normal = if matches!(part, Part::Top) { Vec3::new(0.0, 1.0, 0.0) } else { Vec3::new(0.0, -1.0, 0.0) };
+                let u = 0.5 + local.x / (2.0 * self.radius);
+                let v = 0.5 + local.z / (2.0 * self.radius);
+                (normal, (u, v))
+            }
+        }
+    }
+}
+
+impl RayIntersect for Cylinder {
+    fn ray_intersect(&self, ray: &Ray) -> Intersect<'_> {
+        stats::record_test(Primitive::Cylinder);
+        let local_origin = ray.origin - self.base;
+        let (enter, exit) = match self.interval(&local_origin, &ray.direction) {
+            Some(interval) => interval,
+            None => return Intersect::no_hit(),
+        };
+
+        let (distance, part) = if ray.contains(enter.0) {
+            enter
+        } else if ray.contains(exit.0) {
+            exit
+        } else {
+            return Intersect::no_hit();
+        };
+
+        self.hit_at(&local_origin, &ray.direction, (distance, part))
+    }
+
+    fn intervals(&self, ray: &Ray) -> Vec<(Intersect<'_>, Intersect<'_>)> {
+        stats::record_test(Primitive::Cylinder);
+        let local_origin = ray.origin - self.base;
+        match self.interval(&local_origin, &ray.direction) {
+            Some((enter, exit)) => vec![(
+                self.hit_at(&local_origin, &ray.direction, enter),
+                self.hit_at(&local_origin, &ray.direction, exit),
+            )],
+            None => Vec::new(),
+        }
+    }
+
+    fn occluded(&self, ray: &Ray) -> bool {
+        stats::record_test(Primitive::Cylinder);
+        let local_origin = ray.origin - self.base;
+        self.interval(&local_origin, &ray.direction)
+            .is_some_and(|(enter, exit)| ray.contains(enter.0) || ray.contains(exit.0))
+    }
+}
@@ -0,0 +1,55 @@
+use nalgebra_glm::Vec3;
+use crate::color::Color;
+
+const MIST_WIDTH: f32 = 0.1;  // Fracción del día que dura la neblina alrededor del amanecer
+const MIN_SLOPE: f32 = 1e-4;  // Por debajo, el rayo se trata como horizontal en la niebla por altura
+
+// Medio participante que llena la escena. La densidad cae exponencialmente con la altura; con
+// `height_falloff` en cero la niebla es homogénea.
+#[derive(Clone, Copy)]
+pub struct Fog {
+    pub density: f32,         // Extinción por unidad de distancia a la altura base
+    pub height_falloff: f32,  // Cuánto se adelgaza por unidad de altura
+    pub base_height: f32,
+    pub color: Color,         // Fracción de la luz que dispersa cada canal
+    pub anisotropy: f32,      // Entre -1 y 1; positiva dispersa hacia adelante y marca los haces del sol
+    pub morning_mist: f32,    // Densidad extra, como múltiplo de la normal, al amanecer
+}
+
+impl Fog {
+    // La niebla en el instante `time`: más espesa al amanecer y se disipa en la mañana
+    pub fn at(&self, time: f32, day_duration: f32) -> Fog {
+        let time_factor = time.rem_euclid(day_duration) / day_duration;
+        let since_sunrise = time_factor.min(1.0 - time_factor);  // También cuenta la última hora de la noche
+        let mist = (-(since_sunrise / MIST_WIDTH).powi(2)).exp();
+        Fog { density: self.density * (1.0 + self.morning_mist * mist), ..*self }
+    }
+
+    pub fn density_at(&self, point: &Vec3) -> f32 {
+        self.density * (-self.height_falloff * (point.y - self.base_height)).exp()
+    }
+
+    // Integral de la densidad a lo largo de `distance` unidades desde `origin` en `direction`
+    pub fn optical_depth(&self, origin: &Vec3, direction: &Vec3, distance: f32) -> f32 {
+        let slope = self.height_falloff * direction.y;
+        let along = if slope.abs() < MIN_SLOPE {
+            distance
+        } else {
+            (1.0 - (-slope * distance).exp()) / slope
+        };
+        self.density_at(origin) * along
+    }
+
+    // Fracción de la luz que atraviesa el tramo sin ser absorbida ni desviada
+    pub fn transmittance(&self, origin: &Vec3, direction: &Vec3, distance: f32) -> f32 {
+        (-self.optical_depth(origin, direction, distance)).exp()
+    }
+
+    // Henyey-Greenstein multiplicada por 4π para que la dispersión isótropa valga 1. `cos_theta` es el
+    // coseno entre la dirección del rayo y la dirección hacia la luz.
+    pub fn phase(&self, cos_theta: f32) -> f32 {
+        let g = self.anisotropy.clamp(-0.99, 0.99);
+        let denominator = 1.0 + g * g - 2.0 * g * cos_theta;
+        (1.0 - g * g) / (denominator * denominator.sqrt())
+    }
+}
@@ -0,0 +1,150 @@
+use crate::framebuffer::Framebuffer;
+
+pub const GLYPH_WIDTH: usize = 5;
+pub const GLYPH_HEIGHT: usize = 7;
+pub const ADVANCE: usize = GLYPH_WIDTH + 1;  // Un píxel de separación entre letras
+pub const LINE_HEIGHT: usize = GLYPH_HEIGHT + 2;
+
+// Fuente de mapa de bits de 5x7 para ASCII imprimible (del 32 al 126). Cada byte es una
+// fila, de arriba hacia abajo, y el bit 4 es la columna izquierda.
+static GLYPHS: [[u8; GLYPH_HEIGHT]; 95] = [
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00],  // ' '
+    [0x04, 0x04, 0x04, 0x04, 0x04, 0x00, 0x04],  // '!'
+    [0x0A, 0x0A, 0x0A, 0x00, 0x00, 0x00, 0x00],  // '"'
+    [0x0A, 0x0A, 0x1F, 0x0A, 0x1F, 0x0A, 0x0A],  // '#'
+    [0x04, 0x0F, 0x14, 0x0E, 0x05, 0x1E, 0x04],  // '$'
+    [0x18, 0x19, 0x02, 0x04, 0x08, 0x13, 0x03],  // '%'
+    [0x0C, 0x12, 0x14, 0x08, 0x15, 0x12, 0x0D],  // '&'
+    [0x04, 0x04, 0x04, 0x00, 0x00, 0x00, 0x00],  // '\''
+    [0x02, 0x04, 0x08, 0x08, 0x08, 0x04, 0x02],  // '('
+    [0x08, 0x04, 0x02, 0x02, 0x02, 0x04, 0x08],  // ')'
+    [0x00, 0x04, 0x15, 0x0E, 0x15, 0x04, 0x00],  // '*'
+    [0x00, 0x04, 0x04, 0x1F, 0x04, 0x04, 0x00],  // '+'
+    [0x00, 0x00, 0x00, 0x00, 0x0C, 0x04, 0x08],  // ','
+    [0x00, 0x00, 0x00, 0x1F, 0x00, 0x00, 0x00],  // '-'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x0C, 0x0C],  // '.'
+    [0x00, 0x01, 0x02, 0x04, 0x08, 0x10, 0x00],  // '/'
+    [0x0E, 0x11, 0x13, 0x15, 0x19, 0x11, 0x0E],  // '0'
+    [0x04, 0x0C, 0x04, 0x04, 0x04, 0x04, 0x0E],  // '1'
+    [0x0E, 0x11, 0x01, 0x02, 0x04, 0x08, 0x1F],  // '2'
+    [0x1F, 0x02, 0x04, 0x02, 0x01, 0x11, 0x0E],  // '3'
+    [0x02, 0x06, 0x0A, 0x12, 0x1F, 0x02, 0x02],  // '4'
+    [0x1F, 0x10, 0x1E, 0x01, 0x01, 0x11, 0x0E],  // '5'
+    [0x06, 0x08, 0x10, 0x1E, 0x11, 0x11, 0x0E],  // '6'
+    [0x1F, 0x01, 0x02, 0x04, 0x08, 0x08, 0x08],  // '7'
+    [0x0E, 0x11, 0x11, 0x0E, 0x11, 0x11, 0x0E],  // '8'
+    [0x0E, 0x11, 0x11, 0x0F, 0x01, 0x02, 0x0C],  // '9'
+    [0x00, 0x0C, 0x0C, 0x00, 0x0C, 0x0C, 0x00],  // ':'
+    [0x00, 0x0C, 0x0C, 0x00, 0x0C, 0x04, 0x08],  // ';'
+    [0x02, 0x04, 0x08, 0x10, 0x08, 0x04, 0x02],  // '<'
+    [0x00, 0x00, 0x1F, 0x00, 0x1F, 0x00, 0x00],  // '='
+    [0x08, 0x04, 0x02, 0x01, 0x02, 0x04, 0x08],  // '>'
+    [0x0E, 0x11, 0x01, 0x02, 0x04, 0x00, 0x04],  // '?'
+    [0x0E, 0x11, 0x01, 0x0D, 0x15, 0x15, 0x0E],  // '@'
+    [0x0E, 0x11, 0x11, 0x1F, 0x11, 0x11, 0x11],  // 'A'
+    [0x1E, 0x11, 0x11, 0x1E, 0x11, 0x11, 0x1E],  // 'B'
+    [0x0E, 0x11, 0x10, 0x10, 0x10, 0x11, 0x0E],  // 'C'
+    [0x1C, 0x12, 0x11, 0x11, 0x11, 0x12, 0x1C],  // 'D'
+    [0x1F, 0x10, 0x10, 0x1E, 0x10, 0x10, 0x1F],  // 'E'
+    [0x1F, 0x10, 0x10, 0x1E, 0x10, 0x10, 0x10],  // 'F'
+    [0x0E, 0x11, 0x10, 0x17, 0x11, 0x11, 0x0F],  // 'G'
+    [0x11, 0x11, 0x11, 0x1F, 0x11, 0x11, 0x11],  // 'H'
+    [0x0E, 0x04, 0x04, 0x04, 0x04, 0x04, 0x0E],  // 'I'
+    [0x07, 0x02, 0x02, 0x02, 0x02, 0x12, 0x0C],  // 'J'
+    [0x11, 0x12, 0x14, 0x18, 0x14, 0x12, 0x11],  // 'K'
+    [0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x1F],  // 'L'
+    [0x11, 0x1B, 0x15, 0x15, 0x11, 0x11, 0x11],  // 'M'
+    [0x11, 0x11, 0x19, 0x15, 0x13, 0x11, 0x11],  // 'N'
+    [0x0E, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0E],  // 'O'
+    [0x1E, 0x11, 0x11, 0x1E, 0x10, 0x10, 0x10],  // 'P'
+    [0x0E, 0x11, 0x11, 0x11, 0x15, 0x12, 0x0D],  // 'Q'
+    [0x1E, 0x11, 0x11, 0x1E, 0x14, 0x12, 0x11],  // 'R'
+    [0x0F, 0x10, 0x10, 0x0E, 0x01, 0x01, 0x1E],  // 'S'
+    [0x1F, 0x04, 0x04, 0x04, 0x04, 0x04, 0x04],  // 'T'
+    [0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0E],  // 'U'
+    [0x11, 0x11, 0x11, 0x11, 0x11, 0x0A, 0x04],  // 'V'
+    [0x11, 0x11, 0x11, 0x15, 0x15, 0x15, 0x0A],  // 'W'
+    [0x11, 0x11, 0x0A, 0x04, 0x0A, 0x11, 0x11],  // 'X'
+    [0x11, 0x11, 0x11, 0x0A, 0x04, 0x04, 0x04],  // 'Y'
+    [0x1F, 0x01, 0x02, 0x04, 0x08, 0x10, 0x1F],  // 'Z'
+    [0x0E, 0x08, 0x08, 0x08, 0x08, 0x08, 0x0E],  // '['
+    [0x00, 0x10, 0x08, 0x04, 0x02, 0x01, 0x00],  // '\\'
+    [0x0E, 0x02, 0x02, 0x02, 0x02, 0x02, 0x0E],  // ']'
+    [0x04, 0x0A, 0x11, 0x00, 0x00, 0x00, 0x00],  // '^'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x1F],  // '_'
+    [0x08, 0x04, 0x02, 0x00, 0x00, 0x00, 0x00],  // '`'
+    [0x00, 0x00, 0x0E, 0x01, 0x0F, 0x11, 0x0F],  // 'a'
+    [0x10, 0x10, 0x16, 0x19, 0x11, 0x11, 0x1E],  // 'b'
+    [0x00, 0x00, 0x0E, 0x10, 0x10, 0x11, 0x0E],  // 'c'
+    [0x01, 0x01, 0x0D, 0x13, 0x11, 0x11, 0x0F],  // 'd'
+    [0x00, 0x00, 0x0E, 0x11, 0x1F, 0x10, 0x0E],  // 'e'
+    [0x06, 0x09, 0x08, 0x1C, 0x08, 0x08, 0x08],  // 'f'
+    [0x00, 0x0F, 0x11, 0x11, 0x0F, 0x01, 0x0E],  // 'g'
+    [0x10, 0x10, 0x16, 0x19, 0x11, 0x11, 0x11],  // 'h'
+    [0x04, 0x00, 0x0C, 0x04, 0x04, 0x04, 0x0E],  // 'i'
+    [0x02, 0x00, 0x06, 0x02, 0x02, 0x12, 0x0C],  // 'j'
+    [0x10, 0x10, 0x12, 0x14, 0x18, 0x14, 0x12],  // 'k'
+    [0x0C, 0x04, 0x04, 0x04, 0x04, 0x04, 0x0E],  // 'l'
+    [0x00, 0x00, 0x1A, 0x15, 0x15, 0x11, 0x11],  // 'm'
+    [0x00, 0x00, 0x16, 0x19, 0x11, 0x11, 0x11],  // 'n'
+    [0x00, 0x00, 0x0E, 0x11, 0x11, 0x11, 0x0E],  // 'o'
+    [0x00, 0x00, 0x1E, 0x11, 0x1E, 0x10, 0x10],  // 'p'
+    [0x00, 0x00, 0x0D, 0x13, 0x0F, 0x01, 0x01],  // 'q'
+    [0x00, 0x00, 0x16, 0x19, 0x10, 0x10, 0x10],  // 'r'
+    [0x00, 0x00, 0x0E, 0x10, 0x0E, 0x01, 0x1E],  // 's'
+    [0x08, 0x08, 0x1C, 0x08, 0x08, 0x09, 0x06],  // 't'
+    [0x00, 0x00, 0x11, 0x11, 0x11, 0x13, 0x0D],  // 'u'
+    [0x00, 0x00, 0x11, 0x11, 0x11, 0x0A, 0x04],  // 'v'
+    [0x00, 0x00, 0x11, 0x11, 0x15, 0x15, 0x0A],  // 'w'
+    [0x00, 0x00, 0x11, 0x0A, 0x04, 0x0A, 0x11],  // 'x'
+    [0x00, 0x00, 0x11, 0x11, 0x0F, 0x01, 0x0E],  // 'y'
+    [0x00, 0x00, 0x1F, 0x02, 0x04, 0x08, 0x1F],  // 'z'
+    [0x02, 0x04, 0x04, 0x08, 0x04, 0x04, 0x02],  // '{'
+    [0x04, 0x04, 0x04, 0x04, 0x04, 0x04, 0x04],  // '|'
+    [0x08, 0x04, 0x04, 0x02, 0x04, 0x04, 0x08],  // '}'
+    [0x00, 0x00, 0x08, 0x15, 0x02, 0x00, 0x00],  // '~'
+];
+
+// Las vocales acentuadas y la eñe se dibujan con su letra sin acento
+fn plain(character: char) -> char {
+    match character {
+        'á' => 'a', 'é' => 'e', 'í' => 'i', 'ó' => 'o', 'ú' | 'ü' => 'u', 'ñ' => 'n',
+        'Á' => 'A', 'É' => 'E', 'Í' => 'I', 'Ó' => 'O', 'Ú' | 'Ü' => 'U', 'Ñ' => 'N',
+        other => other,
+    }
+}
+
+fn glyph(character: char) -> &'static [u8; GLYPH_HEIGHT] {
+    let code = plain(character) as usize;
+    if (32..127).contains(&code) {
+        &GLYPHS[code - 32]
+    } else {
+        &GLYPHS['?' as usize - 32]
+    }
+}
+
+// Ancho en píxeles que ocupa una línea de texto
+pub fn text_width(text: &str, scale: usize) -> usize {
+    text.chars().count() * ADVANCE * scale
+}
+
+// Dibuja una línea de texto con su esquina superior izquierda en (x, y); cada píxel de la
+// fuente ocupa un cuadrado de `scale` x `scale`. Lo que cae fuera del framebuffer se recorta.
+pub fn draw_text(framebuffer: &mut Framebuffer, text: &str, x: usize, y: usize, color: u32, scale: usize) {
+    framebuffer.set_current_color(color);
+    for (index, character) in text.chars().enumerate() {
+        let left = x + index * ADVANCE * scale;
+        for (row, bits) in glyph(character).iter().enumerate() {
+            for column in 0..GLYPH_WIDTH {
+                if bits & (0x10 >> column) == 0 {
+                    continue;
+                }
+                for dy in 0..scale {
+                    for dx in 0..scale {
+                        framebuffer.point(left + column * scale + dx, y + row * scale + dy);
+                    }
+                }
+            }
+        }
+    }
+}
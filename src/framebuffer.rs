@@ -30,6 +30,37 @@ impl Framebuffer {
         }
     }
 
+    // Cambia el tamaño del búfer; el contenido anterior se descarta
+    pub fn resize(&mut self, width: usize, height: usize) {
+        if width == self.width && height == self.height {
+            return;
+        }
+        self.width = width;
+        self.height = height;
+        self.buffer = vec![self.background_color; width * height];
+    }
+
+    // Copia la imagen en otro framebuffer de cualquier tamaño repitiendo el píxel más cercano
+    pub fn upscale_into(&self, target: &mut Framebuffer) {
+        for y in 0..target.height {
+            let source_row = (y * self.height / target.height) * self.width;
+            for x in 0..target.width {
+                target.buffer[y * target.width + x] = self.buffer[source_row + x * self.width / target.width];
+            }
+        }
+    }
+
+    // Multiplica el brillo de un rectángulo por `factor`, recortándolo a los bordes
+    pub fn darken(&mut self, x: usize, y: usize, width: usize, height: usize, factor: f32) {
+        for row in y..(y + height).min(self.height) {
+            for column in x..(x + width).min(self.width) {
+                let pixel = &mut self.buffer[row * self.width + column];
+                let scale = |shift: u32| ((((*pixel >> shift) & 0xFF) as f32 * factor) as u32) << shift;
+                *pixel = scale(16) | scale(8) | scale(0);
+            }
+        }
+    }
+
     pub fn set_background_color(&mut self, color: u32) {
         self.background_color = color;
     }
@@ -37,4 +68,14 @@ impl Framebuffer {
     pub fn set_current_color(&mut self, color: u32) {
         self.current_color = color;
     }
+
+    // Guarda el contenido como imagen PPM binaria (P6), legible por casi cualquier visor
+    pub fn save_ppm(&self, path: &str) -> std::io::Result<()> {
+        let mut data = format!("P6\n{} {}\n255\n", self.width, self.height).into_bytes();
+        data.reserve(self.buffer.len() * 3);
+        for pixel in &self.buffer {
+            data.extend_from_slice(&[(pixel >> 16) as u8, (pixel >> 8) as u8, *pixel as u8]);
+        }
+        std::fs::write(path, data)
+    }
 }
@@ -0,0 +1,43 @@
+use crate::font::{self, ADVANCE, LINE_HEIGHT};
+use crate::framebuffer::Framebuffer;
+
+const SCALE: usize = 2;  // Cada píxel de la fuente ocupa 2x2 píxeles de la ventana
+const MARGIN: usize = 8;
+const PADDING: usize = 6;
+const TEXT_COLOR: u32 = 0xFFFFFF;
+const ERROR_COLOR: u32 = 0xFF6060;
+const PANEL_DARKEN: f32 = 0.45;  // Fracción del brillo que conserva la imagen detrás del panel
+
+fn panel_height(lines: usize) -> usize {
+    lines * LINE_HEIGHT * SCALE + 2 * PADDING
+}
+
+// Panel oscurecido con su esquina superior izquierda en (x, y)
+fn panel(screen: &mut Framebuffer, lines: &[String], x: usize, y: usize, color: u32) {
+    let text_width = lines.iter().map(|line| font::text_width(line, SCALE)).max().unwrap_or(0);
+    screen.darken(x, y, text_width + 2 * PADDING, panel_height(lines.len()), PANEL_DARKEN);
+
+    for (index, line) in lines.iter().enumerate() {
+        font::draw_text(screen, line, x + PADDING, y + PADDING + index * LINE_HEIGHT * SCALE, color, SCALE);
+    }
+}
+
+// Dibuja las líneas en un panel oscurecido en la esquina superior izquierda
+pub fn draw(screen: &mut Framebuffer, lines: &[String]) {
+    panel(screen, lines, MARGIN, MARGIN, TEXT_COLOR);
+}
+
+// Muestra un error en la esquina inferior izquierda, partido en líneas que quepan en la pantalla
+pub fn error(screen: &mut Framebuffer, message: &str) {
+    let columns = (screen.width.saturating_sub(2 * (MARGIN + PADDING)) / (ADVANCE * SCALE)).max(1);
+    let characters: Vec<char> = message.chars().collect();
+    let lines: Vec<String> = characters.chunks(columns).map(|chunk| chunk.iter().collect()).collect();
+    let y = screen.height.saturating_sub(MARGIN + panel_height(lines.len()));
+    panel(screen, &lines, MARGIN, y, ERROR_COLOR);
+}
+
+// Hora del reloj para un instante del ciclo: el sol sale a las 6:00 y se pone a las 18:00
+pub fn clock(time: f32, day_duration: f32) -> String {
+    let hours = (6.0 + time.rem_euclid(day_duration) / day_duration * 24.0) % 24.0;
+    format!("{:02}:{:02}", hours as u32, (hours.fract() * 60.0) as u32)
+}
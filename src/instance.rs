@@ -0,0 +1,134 @@
+use std::sync::Arc;
+use nalgebra_glm::{Mat4, Vec3, Vec4};
+use crate::ray::Ray;
+use crate::ray_intersect::{Intersect, RayIntersect};
+
+// Matriz de la instancia junto con las derivadas que se usan al intersectar
+#[derive(Clone, Copy)]
+struct Frame {
+    transform: Mat4,
+    inverse: Mat4,
+    normal_matrix: Mat4,
+}
+
+// Inversa de la transformación, o `None` si no la tiene: escala cero, o tan chica o tan grande
+// que la inversa se sale del rango de f32
+pub fn inverse(transform: &Mat4) -> Option<Mat4> {
+    transform.try_inverse()
+        .filter(|inverse| transform.iter().chain(inverse.iter()).all(|value| value.is_finite()))
+}
+
+impl Frame {
+    fn new(transform: Mat4) -> Option<Self> {
+        let inverse = inverse(&transform)?;
+        Some(Frame {
+            transform,
+            inverse,
+            normal_matrix: inverse.transpose(),
+        })
+    }
+}
+
+// Envuelve cualquier primitiva con una transformación 4x4. El objeto se comparte
+// con `Arc`, así que muchas copias de la misma geometría no duplican memoria.
+// Una instancia animada calcula su transformación en el instante de cada rayo.
+pub struct Instance {
+    pub object: Arc<dyn RayIntersect>,
+    frame: Frame,
+    motion: Option<Box<dyn Fn(f32) -> Mat4>>,
+}
+
+impl Instance {
+    // Devuelve `None` si la transformación es singular: no se podría llevar el rayo al objeto
+    pub fn new(object: Arc<dyn RayIntersect>, transform: Mat4) -> Option<Self> {
+        Some(Instance {
+            object,
+            frame: Frame::new(transform)?,
+            motion: None,
+        })
+    }
+
+    pub fn animated(object: Arc<dyn RayIntersect>, motion: impl Fn(f32) -> Mat4 + 'static) -> Option<Self> {
+        Some(Instance {
+            object,
+            frame: Frame::new(motion(0.0))?,
+            motion: Some(Box::new(motion)),
+        })
+    }
+
+    // Si en algún instante la transformación deja de ser invertible se usa la del inicio
+    fn frame_at(&self, time: f32) -> Frame {
+        match &self.motion {
+            Some(motion) => Frame::new(motion(time)).unwrap_or(self.frame),
+            None => self.frame,
+        }
+    }
+
+    // Llevamos el rayo al espacio del objeto. La dirección se normaliza para que
+    // las primitivas trabajen siempre con rayos unitarios; `scale` convierte la
+    // distancia del espacio del objeto de vuelta al espacio del mundo.
+    fn to_local(frame: &Frame, ray: &Ray) -> Option<(Ray, f32)> {
+        let local_origin = transform_point(&frame.inverse, &ray.origin);
+        let local_direction = transform_vector(&frame.inverse, &ray.direction);
+        let scale = local_direction.magnitude();
+        if scale == 0.0 {
+            return None;
+        }
+        let local_ray = Ray::with_range(local_origin, local_direction / scale, ray.t_min * scale, ray.t_max * scale)
+            .with_time(ray.time);
+        Some((local_ray, scale))
+    }
+
+    fn to_world<'a>(frame: &Frame, mut intersect: Intersect<'a>, scale: f32) -> Intersect<'a> {
+        intersect.distance /= scale;
+        intersect.point = transform_point(&frame.transform, &intersect.point);
+        intersect.normal = transform_vector(&frame.normal_matrix, &intersect.normal).normalize();
+        intersect
+    }
+}
+
+fn transform_point(matrix: &Mat4, point: &Vec3) -> Vec3 {
+    let p = matrix * Vec4::new(point.x, point.y, point.z, 1.0);
+    Vec3::new(p.x, p.y, p.z)
+}
+
+fn transform_vector(matrix: &Mat4, vector: &Vec3) -> Vec3 {
+    let v = matrix * Vec4::new(vector.x, vector.y, vector.z, 0.0);
+    Vec3::new(v.x, v.y, v.z)
+}
+
+// La instancia no suma pruebas propias: solo cambia de espacio el rayo y la primitiva
+// envuelta cuenta la suya
+impl RayIntersect for Instance {
+    fn ray_intersect(&self, ray: &Ray) -> Intersect<'_> {
+        let frame = self.frame_at(ray.time);
+        let (local_ray, scale) = match Instance::to_local(&frame, ray) {
+            Some(local) => local,
+            None => return Intersect::no_hit(),
+        };
+
+        let intersect = self.object.ray_intersect(&local_ray);
+        if !intersect.is_intersecting {
+            return intersect;
+        }
+        Instance::to_world(&frame, intersect, scale)
+    }
+
+    fn intervals(&self, ray: &Ray) -> Vec<(Intersect<'_>, Intersect<'_>)> {
+        let frame = self.frame_at(ray.time);
+        let (local_ray, scale) = match Instance::to_local(&frame, ray) {
+            Some(local) => local,
+            None => return Vec::new(),
+        };
+
+        self.object.intervals(&local_ray)
+            .into_iter()
+            .map(|(enter, exit)| (Instance::to_world(&frame, enter, scale), Instance::to_world(&frame, exit, scale)))
+            .collect()
+    }
+
+    fn occluded(&self, ray: &Ray) -> bool {
+        let frame = self.frame_at(ray.time);
+        Instance::to_local(&frame, ray).is_some_and(|(local_ray, _)| self.object.occluded(&local_ray))
+    }
+}
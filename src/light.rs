@@ -2,6 +2,7 @@ use nalgebra_glm::Vec3;
 use crate::color::Color;
 use std::f32::consts::PI;
 
+#[derive(Clone)]
 pub struct Light {
     pub position: Vec3,
     pub color: Color,
@@ -17,22 +18,21 @@ impl Light {
         }
     }
 
-    // Función para actualizar el ciclo día/noche
-    pub fn update_day_night_cycle(&mut self, elapsed_time: f32, day_duration: f32) {
-        let time_factor = (elapsed_time % day_duration) / day_duration;
+    // El sol del ciclo de día y noche en el instante `time`
+    pub fn sun_at(time: f32, day_duration: f32) -> Self {
+        let time_factor = (time.rem_euclid(day_duration)) / day_duration;
         let light_angle = time_factor * 2.0 * PI;
 
-        // Actualizamos la posición de la luz para simular el movimiento del sol
-        self.position.x = light_angle.cos() * 10.0;
-        self.position.y = light_angle.sin() * 10.0;
+        // La posición recorre un círculo para simular el movimiento del sol
+        let position = Vec3::new(light_angle.cos() * 10.0, light_angle.sin() * 10.0, 5.0);
 
-        // Cambiamos el color de la luz según la hora del día
-        if time_factor < 0.5 {
-            // Simulación de amanecer
-            self.color = Color::new(255, 255, 224);  // Luz más cálida (amanecer)
+        // Cambiamos el color y la intensidad de la luz según la hora del día
+        if time_factor < 0.25 {
+            Light::new(position, Color::new(255, 223, 186), 1.2)  // Amanecer
+        } else if time_factor < 0.75 {
+            Light::new(position, Color::new(255, 255, 224), 1.5)  // Mediodía
         } else {
-            // Simulación de atardecer
-            self.color = Color::new(255, 140, 0);  // Luz más anaranjada (atardecer)
+            Light::new(position, Color::new(255, 140, 0), 1.0)  // Atardecer
         }
     }
 }
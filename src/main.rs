@@ -1,4 +1,5 @@
 mod framebuffer;
+mod ray;
 mod ray_intersect;
 mod sphere;
 mod color;
@@ -6,25 +7,90 @@ mod camera;
 mod light;
 mod material;
 mod cube;
+mod instance;
+mod cylinder;
+mod cone;
+mod torus;
+mod capsule;
+mod csg;
+mod random;
+mod camera_path;
+mod timeline;
+mod video;
+mod resolution;
+mod font;
+mod hud;
+mod stats;
+mod render_mode;
+mod scene;
+mod settings;
+mod cli;
+mod fog;
 
-use minifb::{Window, WindowOptions, Key};
-use nalgebra_glm::{Vec3, normalize};
-use std::time::{Duration, Instant};
+use minifb::{Window, WindowOptions, Key, KeyRepeat, MouseButton, MouseMode};
+use nalgebra_glm::Vec3;
+use std::time::{Duration, Instant, SystemTime};
 use std::f32::consts::PI;
 
 use crate::color::Color;
+use crate::ray::Ray;
 use crate::ray_intersect::{Intersect, RayIntersect};
-use crate::sphere::Sphere;
 use crate::framebuffer::Framebuffer;
-use crate::camera::Camera;
+use crate::camera::{Bokeh, Camera, CameraMode, Projection, DEFAULT_FOV};
 use crate::light::Light;
-use crate::material::Material;
-use crate::cube::Cube;
+use crate::material::{Absorption, Material};
+use crate::fog::Fog;
+use crate::random::Rng;
+use crate::camera_path::{CameraKey, CameraPath, Spline};
+use crate::timeline::Timeline;
+use crate::resolution::DynamicResolution;
+use crate::stats::RenderStats;
+use crate::render_mode::{RenderMode, RENDER_MODES};
+use crate::scene::Scene;
+use crate::settings::{RenderSettings, Termination};
+use crate::cli::{Command, Options};
 
 const ORIGIN_BIAS: f32 = 1e-4;
+const DEPTH_RANGE: f32 = 20.0;  // Distancia que se ve negra en la vista de profundidad
+const HEATMAP_RANGE: f32 = 200.0;  // Pruebas de intersección por muestra que se ven rojas en el mapa de calor
+const MAX_NESTED_MEDIA: usize = 4;  // Objetos transparentes uno dentro de otro que se distinguen
+const MIN_SURVIVAL: f32 = 0.05;  // Probabilidad mínima de seguir con ruleta rusa, para que ningún camino pese demasiado
+const HIGHLIGHT_COLOR: Color = Color::new(255, 140, 0);  // Tinte del objeto elegido con el clic izquierdo
 const AMBIENT_LIGHT_DAY: f32 = 0.1;
 const AMBIENT_LIGHT_NIGHT: f32 = 0.05;
-const DAY_DURATION: f32 = 10.0;  // Duración del ciclo de día y noche en segundos
+const FOG_AMBIENT: f32 = 0.6;  // Brillo de la niebla iluminada solo por el cielo, de día
+const FOG_MAX_DISTANCE: f32 = 20.0;  // Tramo de niebla que atraviesan los rayos que van al cielo
+const ZOOM_STEP: f32 = 1.05;  // Factor de zoom por cuadro mientras se mantiene la tecla
+const APERTURE_STEP: f32 = 0.01;
+const MAX_APERTURE: f32 = 0.5;
+const SHUTTER_TIME: f32 = 0.1;  // Segundos que el obturador queda abierto con M
+const LOOK_SPEED: f32 = PI / 60.0;  // Giro por cuadro con las flechas en modo libre
+const MOUSE_SENSITIVITY: f32 = 0.005;  // Radianes por píxel de movimiento del mouse
+const MIN_MOVE_SPEED: f32 = 0.01;
+const MAX_MOVE_SPEED: f32 = 2.0;
+const DEFAULT_PATH_FILE: &str = "camera_path.txt";
+const DEFAULT_SCENE_FILE: &str = "scene.txt";
+const DEFAULT_SCENE: &str = include_str!("../scene.txt");  // Se usa si no existe scene.txt
+const MOVE_STEP: f32 = 0.05;  // Desplazamiento por cuadro del objeto seleccionado
+const SCALE_STEP: f32 = 1.02;  // Factor de escala por cuadro del objeto seleccionado
+const DUPLICATE_OFFSET: f32 = 0.5;  // Separación de la copia hacia la derecha de la cámara
+const PATH_DURATION: f32 = 10.0;  // Duración por defecto de un recorrido nuevo
+const RECORD_INTERVAL: f32 = 0.5;  // Segundos entre puntos de control al grabar
+const TARGET_FPS: f32 = 30.0;  // Cuadros por segundo que intenta mantener la ventana
+const IDLE_DELAY: f32 = 0.5;  // Segundos sin tocar nada antes de volver a la resolución completa
+const RELOAD_INTERVAL: f32 = 0.25;  // Segundos entre revisiones del archivo de escena
+
+// Archivo donde F2 guarda la escena editada: scene.txt pasa a scene.edited.txt
+fn edited_scene_path(scene_path: &str) -> String {
+    let path = std::path::Path::new(scene_path);
+    let extension = path.extension().map_or("txt".to_string(), |extension| extension.to_string_lossy().into_owned());
+    path.with_extension(format!("edited.{}", extension)).to_string_lossy().into_owned()
+}
+
+// Fecha de modificación del archivo, o `None` si no existe
+fn modified_time(path: &str) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
 
 fn offset_origin(intersect: &Intersect, direction: &Vec3) -> Vec3 {
     let offset = intersect.normal * ORIGIN_BIAS;
@@ -40,7 +106,7 @@ fn reflect(incident: &Vec3, normal: &Vec3) -> Vec3 {
 }
 
 fn refract(incident: &Vec3, normal: &Vec3, eta_t: f32) -> Vec3 {
-    let cosi = -incident.dot(normal).max(-1.0).min(1.0);
+    let cosi = -incident.dot(normal).clamp(-1.0, 1.0);
     let (n_cosi, eta, n_normal);
 
     if cosi < 0.0 {
@@ -82,83 +148,330 @@ fn realistic_texture(u: f32, v: f32) -> Color {
     Color::new(r, g, b)  // Genera una textura más suave y realista
 }
 
+// Verdadero si algún objeto bloquea el rayo dentro de su intervalo; se detiene en el primero
+fn occluded(objects: &[Box<dyn RayIntersect>], ray: &Ray) -> bool {
+    objects.iter().any(|object| object.occluded(ray))
+}
+
+// Índice del objeto más cercano que corta el rayo, junto con el impacto. Cada impacto acorta
+// el intervalo del rayo, así que los objetos más lejanos se descartan solos.
+fn pick<'a>(objects: &'a [Box<dyn RayIntersect>], ray: &Ray) -> Option<(usize, Intersect<'a>)> {
+    let mut ray = *ray;
+    let mut picked = None;
+
+    for (index, object) in objects.iter().enumerate() {
+        let i = object.ray_intersect(&ray);
+        if i.is_intersecting {
+            ray.t_max = i.distance;
+            picked = Some((index, i));
+        }
+    }
+
+    picked
+}
+
+fn closest_hit<'a>(objects: &'a [Box<dyn RayIntersect>], ray: &Ray) -> Intersect<'a> {
+    pick(objects, ray).map_or(Intersect::empty(), |(_, intersect)| intersect)
+}
+
+// Descripción del objeto elegido con el clic izquierdo, para la consola
+fn describe_pick(index: usize, intersect: &Intersect) -> String {
+    let material = intersect.material;
+    let vector = |v: &Vec3| format!("({:.3}, {:.3}, {:.3})", v.x, v.y, v.z);
+    format!(
+        "Objeto {} | material \"{}\": {}, brillo {}, albedo {:?}, índice de refracción {}\n  punto {}  normal {}  distancia {:.3}",
+        index,
+        material.name,
+        material.color,
+        material.shininess,
+        material.albedo,
+        material.refractive_index,
+        vector(&intersect.point),
+        vector(&intersect.normal),
+        intersect.distance,
+    )
+}
+
 fn cast_shadow(
     intersect: &Intersect,
     light: &Light,
     objects: &[Box<dyn RayIntersect>],
+    time: f32,
 ) -> f32 {
     let light_dir = (light.position - intersect.point).normalize();
     let light_distance = (light.position - intersect.point).magnitude();
     let shadow_ray_origin = offset_origin(intersect, &light_dir);
-    let mut shadow_intensity = 0.0;
+    let shadow_ray = Ray::with_range(shadow_ray_origin, light_dir, 0.0, light_distance).with_time(time);
+    stats::record_shadow();
 
-    for object in objects {
-        let shadow_intersect = object.ray_intersect(&shadow_ray_origin, &light_dir);
-        if shadow_intersect.is_intersecting && shadow_intersect.distance < light_distance {
-            let distance_ratio = shadow_intersect.distance / light_distance;
-            shadow_intensity = 1.0 - distance_ratio.powf(2.0).min(1.0);
-            break;
-        }
+    // La consulta rápida descarta los puntos iluminados; solo en sombra se busca el objeto
+    // que tapa más cerca para suavizar la sombra según su distancia
+    if !occluded(objects, &shadow_ray) {
+        return 0.0;
     }
+    let blocker = closest_hit(objects, &shadow_ray);
+    let distance_ratio = if blocker.is_intersecting { blocker.distance / light_distance } else { 0.0 };
+    1.0 - distance_ratio.powf(2.0).min(1.0)
+}
 
-    shadow_intensity
+// Intensidad con la que llega la luz al punto, descontando su sombra y lo que absorbe la niebla
+fn light_reaching(intersect: &Intersect, light: &Light, objects: &[Box<dyn RayIntersect>], fog: Option<&Fog>, time: f32) -> f32 {
+    let shadow_intensity = cast_shadow(intersect, light, objects, time);
+    let light_intensity = light.intensity * (1.0 - shadow_intensity);
+    match fog {
+        Some(fog) => {
+            let to_light = light.position - intersect.point;
+            light_intensity * fog.transmittance(&intersect.point, &to_light.normalize(), to_light.magnitude())
+        }
+        None => light_intensity,
+    }
 }
 
-pub fn cast_ray(
-    ray_origin: &Vec3,
-    ray_direction: &Vec3,
+// Difuso de un material translúcido: la luz que entra en puntos cercanos de la superficie sale
+// también por este, pesada por el perfil de cada canal. Se sondea un disco en el plano tangente
+// con rayos perpendiculares a la superficie que solo aceptan el objeto `object`, así la luz se
+// cuela hacia el lado en sombra y el borde de la sombra toma el color del canal que llega más
+// lejos. Devuelve `None` si el material no es translúcido o la dispersión está desactivada.
+fn subsurface_diffuse(
+    intersect: &Intersect,
+    object: usize,
     objects: &[Box<dyn RayIntersect>],
-    light: &Light,
-    ambient_light: f32,
+    scene: &Scene,
+    settings: &RenderSettings,
+    time: f32,
+    rng: &mut Rng,
+) -> Option<Color> {
+    let subsurface = intersect.material.subsurface.filter(|_| settings.subsurface_samples > 0)?;
+    let fog = scene.fog.map(|fog| fog.at(time, settings.day_duration));
+    let irradiance = |point: &Intersect| {
+        let mut total = [0.0; 3];
+        for source in &scene.lights {
+            let light = source.at(time, settings.day_duration);
+            let facing = point.normal.dot(&(light.position - point.point).normalize());
+            if facing <= 0.0 {
+                continue;
+            }
+            let intensity = facing * light_reaching(point, &light, objects, fog.as_ref(), time);
+            let color = light.color.to_rgb();
+            for channel in 0..3 {
+                total[channel] += color[channel] * intensity;
+            }
+        }
+        total
+    };
+
+    let normal = intersect.normal;
+    let helper = if normal.x.abs() > 0.9 { Vec3::new(0.0, 1.0, 0.0) } else { Vec3::new(1.0, 0.0, 0.0) };
+    let tangent = normal.cross(&helper).normalize();
+    let bitangent = normal.cross(&tangent);
+
+    let mut gathered = [0.0; 3];
+    let mut weights = [0.0; 3];
+    for _ in 0..settings.subsurface_samples {
+        // La distancia sigue el perfil del canal más largo; los demás se pesan por el cociente
+        // entre su perfil y el de ese canal
+        let (distance, density) = subsurface.sample_distance(rng.next_f32(), rng.next_f32());
+        let angle = 2.0 * PI * rng.next_f32();
+        let offset = (tangent * angle.cos() + bitangent * angle.sin()) * distance;
+        let height = distance + ORIGIN_BIAS;
+        let probe = Ray::with_range(intersect.point + offset + normal * height, -normal, 0.0, 2.0 * height).with_time(time);
+        stats::record_subsurface();
+        let hit = match pick(objects, &probe) {
+            Some((index, hit)) if index == object => hit,
+            _ => continue,
+        };
+        let profile = subsurface.profile((hit.point - intersect.point).magnitude());
+        let light = irradiance(&hit);
+        for channel in 0..3 {
+            let weight = profile[channel] / density;
+            gathered[channel] += light[channel] * weight;
+            weights[channel] += weight;
+        }
+    }
+
+    // Si ningún sondeo encontró el material se usa la luz del propio punto
+    if weights.iter().any(|&weight| weight <= 0.0) {
+        gathered = irradiance(intersect);
+        weights = [1.0; 3];
+    }
+    Some(Color::from_rgb([0, 1, 2].map(|channel| gathered[channel] / weights[channel] * intersect.material.albedo[0])))
+}
+
+// Estado de un camino de rayos: cuántos rebotes lleva de cada tipo, cuánto pesa todavía en el
+// color del píxel y dentro de qué objetos transparentes viaja
+#[derive(Clone, Copy)]
+pub struct PathState {
     depth: u32,
-    time: f32,  
-) -> Color {
-    if depth > 3 {
-        return skybox(ray_direction);  // Llamamos a la función skybox aquí
+    reflections: u32,
+    refractions: u32,
+    throughput: f32,
+    media: [Absorption; MAX_NESTED_MEDIA],  // Del más externo al más interno
+    inside: usize,  // Objetos en los que entró y no salió; puede pasar de MAX_NESTED_MEDIA
+}
+
+// Qué hacer con un rayo secundario antes de trazarlo
+enum Continuation {
+    Trace(f32),  // Se traza; el peso compensa los caminos que cortó la ruleta rusa
+    Cut(Color),  // No se traza y aporta este color
+}
+
+impl PathState {
+    const PRIMARY: PathState = PathState {
+        depth: 0,
+        reflections: 0,
+        refractions: 0,
+        throughput: 1.0,
+        media: [Absorption::NONE; MAX_NESTED_MEDIA],
+        inside: 0,
+    };
+
+    fn reflected(self, weight: f32) -> Self {
+        PathState { depth: self.depth + 1, reflections: self.reflections + 1, throughput: self.throughput * weight, ..self }
     }
 
-    let mut intersect = Intersect::empty();
-    let mut zbuffer = f32::INFINITY;
+    fn refracted(self, weight: f32) -> Self {
+        PathState { depth: self.depth + 1, refractions: self.refractions + 1, throughput: self.throughput * weight, ..self }
+    }
 
-    for object in objects {
-        let i = object.ray_intersect(ray_origin, ray_direction);
-        if i.is_intersecting && i.distance < zbuffer {
-            zbuffer = i.distance;
-            intersect = i;
+    // El rayo refractado entra al objeto si cruza la superficie desde afuera y sale si la cruza
+    // desde adentro; con reflexión total interna sigue en el mismo medio
+    fn crossed(mut self, intersect: &Intersect, incident: &Vec3, outgoing: &Vec3) -> Self {
+        let was_inside = incident.dot(&intersect.normal) > 0.0;
+        let is_inside = outgoing.dot(&intersect.normal) < 0.0;
+        if !was_inside && is_inside {
+            if self.inside < MAX_NESTED_MEDIA {
+                self.media[self.inside] = intersect.material.absorption;
+            }
+            self.inside += 1;
+        } else if was_inside && !is_inside {
+            self.inside = self.inside.saturating_sub(1);
         }
+        self
     }
 
-    if !intersect.is_intersecting {
-        return skybox(ray_direction);  // Si no hay intersección, devuelve el skybox
+    // Absorción del objeto transparente más interno en el que está el rayo. Más allá de
+    // MAX_NESTED_MEDIA se repite la del último que se guardó.
+    fn medium(&self) -> Option<Absorption> {
+        (self.inside > 0).then(|| self.media[self.inside.min(MAX_NESTED_MEDIA) - 1])
     }
 
-    let light_dir = (light.position - intersect.point).normalize();
-    let view_dir = (ray_origin - intersect.point).normalize();
-    let reflect_dir = reflect(&-light_dir, &intersect.normal).normalize();
-    
-    let shadow_intensity = cast_shadow(&intersect, light, objects);
-    let light_intensity = light.intensity * (1.0 - shadow_intensity);
-    
-    let diffuse_intensity = intersect.normal.dot(&light_dir).max(0.0).min(1.0);
-    let diffuse = light.color * intersect.material.albedo[0] * diffuse_intensity * light_intensity;
-    
-    let specular_intensity = view_dir.dot(&reflect_dir).max(0.0).powf(intersect.material.shininess);
-    let specular = light.color * intersect.material.albedo[1] * specular_intensity * light_intensity;
-    
+    // Verdadero si no pasó ningún límite de rebotes ni quedó por debajo del aporte mínimo
+    fn within_limits(&self, settings: &RenderSettings) -> bool {
+        self.depth <= settings.max_depth
+            && self.reflections <= settings.max_reflection_depth
+            && self.refractions <= settings.max_refraction_depth
+            && self.throughput >= settings.min_contribution
+    }
+
+    // Los caminos que pasan los límites aportan lo que indique `termination` en vez del cielo.
+    // Con ruleta rusa cada camino sigue con probabilidad igual a su peso y los que siguen se
+    // multiplican por la inversa, así en promedio la imagen no se oscurece.
+    fn continuation(&mut self, settings: &RenderSettings, time: f32, rng: &mut Rng) -> Continuation {
+        if !self.within_limits(settings) {
+            return Continuation::Cut(match settings.termination {
+                Termination::Black => Color::black(),
+                Termination::Ambient => Color::new(255, 255, 255) * ambient_at(time, settings.day_duration),
+            });
+        }
+        if settings.roulette_depth.is_some_and(|start| self.depth > start) {
+            let survival = self.throughput.clamp(MIN_SURVIVAL, 1.0);
+            if rng.next_f32() >= survival {
+                return Continuation::Cut(Color::black());
+            }
+            self.throughput /= survival;
+            return Continuation::Trace(1.0 / survival);
+        }
+        Continuation::Trace(1.0)
+    }
+}
+
+fn reflectivity(material: &Material) -> f32 {
+    material.albedo[2] * 0.8
+}
+
+fn transparency(material: &Material) -> f32 {
+    material.albedo[3] * 0.9
+}
+
+// Devuelve el color que llega por el rayo y el índice del objeto que tocó primero, si tocó alguno
+pub fn cast_ray(
+    ray: &Ray,
+    objects: &[Box<dyn RayIntersect>],
+    scene: &Scene,
+    settings: &RenderSettings,
+    mut path: PathState,
+    rng: &mut Rng,
+) -> (Color, Option<usize>) {
+    let time = ray.time;
+    let medium = path.medium();
+
+    let Some((object, intersect)) = pick(objects, ray) else {
+        // Si no hay intersección, devuelve el skybox
+        let sky = match medium {
+            Some(_) => skybox(&ray.direction),  // Solo pasa por errores numéricos al salir de un objeto
+            None => through_fog(skybox(&ray.direction), ray, FOG_MAX_DISTANCE, objects, scene, settings, rng),
+        };
+        return (sky, None);
+    };
+
+    // Dentro de un objeto transparente el tramo recorrido absorbe luz según su espesor, y los
+    // rebotes que siguen pesan menos
+    let absorbed = medium.map(|medium| medium.transmittance(intersect.distance));
+    if let Some(absorbed) = absorbed {
+        path.throughput *= absorbed.iter().sum::<f32>() / 3.0;
+    }
+
+    let view_dir = (ray.origin - intersect.point).normalize();
+    let fog = scene.fog.map(|fog| fog.at(time, settings.day_duration));
+
+    // Cada luz suma su aporte difuso y especular si no está tapada. Se evalúan en el instante
+    // del rayo para que el sol también deje estela con el desenfoque de movimiento. En los
+    // materiales translúcidos el difuso sale de la dispersión bajo la superficie.
+    let subsurface = subsurface_diffuse(&intersect, object, objects, scene, settings, time, rng);
+    let mut diffuse = Color::black();
+    let mut specular = Color::black();
+    for source in &scene.lights {
+        let light = &source.at(time, settings.day_duration);
+        let light_dir = (light.position - intersect.point).normalize();
+        let reflect_dir = reflect(&-light_dir, &intersect.normal).normalize();
+
+        let light_intensity = light_reaching(&intersect, light, objects, fog.as_ref(), time);
+
+        if subsurface.is_none() {
+            let diffuse_intensity = intersect.normal.dot(&light_dir).clamp(0.0, 1.0);
+            diffuse = diffuse + light.color * intersect.material.albedo[0] * diffuse_intensity * light_intensity;
+        }
+
+        let specular_intensity = view_dir.dot(&reflect_dir).max(0.0).powf(intersect.material.shininess);
+        specular = specular + light.color * intersect.material.albedo[1] * specular_intensity * light_intensity;
+    }
+    let diffuse = subsurface.unwrap_or(diffuse);
+
+    // Color que llega por un rayo secundario, ya multiplicado por su peso en la superficie
+    let mut secondary = |direction: Vec3, mut next: PathState, weight: f32, record: fn(u32)| {
+        match next.continuation(settings, time, rng) {
+            Continuation::Trace(compensation) => {
+                record(next.depth);
+                let next_ray = Ray::new(offset_origin(&intersect, &direction), direction).with_time(time);
+                cast_ray(&next_ray, objects, scene, settings, next, rng).0 * (weight * compensation)
+            }
+            Continuation::Cut(color) => color * weight,
+        }
+    };
+
     let mut reflect_color = Color::black();
-    let reflectivity = intersect.material.albedo[2] * 0.8;
+    let reflectivity = reflectivity(intersect.material);
     if reflectivity > 0.0 {
-        let reflect_dir = reflect(&ray_direction, &intersect.normal).normalize();
-        let reflect_origin = offset_origin(&intersect, &reflect_dir);
-        reflect_color = cast_ray(&reflect_origin, &reflect_dir, objects, light, ambient_light, depth + 1, time);
+        let reflect_dir = reflect(&ray.direction, &intersect.normal).normalize();
+        reflect_color = secondary(reflect_dir, path.reflected(reflectivity), reflectivity, stats::record_reflection);
     }
     
     let mut refract_color = Color::black();
-    let transparency = intersect.material.albedo[3] * 0.9;
+    let transparency = transparency(intersect.material);
     if transparency > 0.0 {
-        let refract_dir = refract(&ray_direction, &intersect.normal, intersect.material.refractive_index);
-        let refract_origin = offset_origin(&intersect, &refract_dir);
-        refract_color = cast_ray(&refract_origin, &refract_dir, objects, light, ambient_light, depth + 1, time);
+        let refract_dir = refract(&ray.direction, &intersect.normal, intersect.material.refractive_index);
+        let next = path.refracted(transparency).crossed(&intersect, &ray.direction, &refract_dir);
+        refract_color = secondary(refract_dir, next, transparency, stats::record_refraction);
     }
 
     let (u, v) = uv_mapping(&intersect);
@@ -172,12 +485,156 @@ pub fn cast_ray(
         realistic_texture(u, v)  // Textura suave para el muñeco
     };
 
-    let final_color = (texture_color + diffuse + specular) * (1.0 - reflectivity - transparency)
-    + (reflect_color * reflectivity)
-    + (refract_color * transparency)
-    + Color::new(255, 255, 255) * ambient_light;
+    let color = (texture_color + diffuse + specular) * (1.0 - reflectivity - transparency)
+        + reflect_color
+        + refract_color
+        + Color::new(255, 255, 255) * ambient_at(time, settings.day_duration);
+    let color = match absorbed {
+        Some(absorbed) => {
+            let color = color.to_rgb();
+            Color::from_rgb([0, 1, 2].map(|channel| color[channel] * absorbed[channel]))
+        }
+        None => through_fog(color, ray, intersect.distance, objects, scene, settings, rng),
+    };
+    (color, Some(object))
+}
+
+// Lo que queda del color que llega por el rayo desde `distance` más la luz que la niebla dispersa
+// hacia el origen: la del cielo se integra de forma exacta y la de cada luz marchando por el rayo
+// con rayos de sombra, que dejan ver los haces entre los objetos.
+fn through_fog(
+    color: Color,
+    ray: &Ray,
+    distance: f32,
+    objects: &[Box<dyn RayIntersect>],
+    scene: &Scene,
+    settings: &RenderSettings,
+    rng: &mut Rng,
+) -> Color {
+    let fog = match scene.fog {
+        Some(fog) => fog.at(ray.time, settings.day_duration),
+        None => return color,
+    };
+    let transmittance = fog.transmittance(&ray.origin, &ray.direction, distance);
+    let sky = 255.0 * FOG_AMBIENT * ambient_at(ray.time, settings.day_duration) / AMBIENT_LIGHT_DAY;
+    let mut scattered = [sky * (1.0 - transmittance); 3];
 
-    final_color
+    if settings.fog_steps > 0 {
+        // Un desfase al azar por rayo cambia las franjas de la marcha por ruido
+        let step = distance / settings.fog_steps as f32;
+        let offset = rng.next_f32();
+        for source in &scene.lights {
+            let light = source.at(ray.time, settings.day_duration);
+            for i in 0..settings.fog_steps {
+                let t = (i as f32 + offset) * step;
+                let point = ray.at(t);
+                let light_distance = (light.position - point).magnitude();
+                let light_dir = (light.position - point) / light_distance;
+                stats::record_shadow();
+                if occluded(objects, &Ray::with_range(point, light_dir, 0.0, light_distance).with_time(ray.time)) {
+                    continue;
+                }
+                let weight = fog.density_at(&point) * step
+                    * fog.transmittance(&ray.origin, &ray.direction, t)
+                    * fog.transmittance(&point, &light_dir, light_distance)
+                    * fog.phase(ray.direction.dot(&light_dir))
+                    * light.intensity;
+                let light_color = light.color.to_rgb();
+                for channel in 0..3 {
+                    scattered[channel] += light_color[channel] * weight;
+                }
+            }
+        }
+    }
+
+    let color = color.to_rgb();
+    let albedo = fog.color.to_rgb();
+    Color::from_rgb([0, 1, 2].map(|channel| color[channel] * transmittance + scattered[channel] * albedo[channel] / 255.0))
+}
+
+// Rampa azul, cian, verde, amarillo y rojo para t entre 0 y 1
+fn heat_color(t: f32) -> Color {
+    let t = t.clamp(0.0, 1.0) * 4.0;
+    let green = if t < 1.0 { t } else if t < 3.0 { 1.0 } else { 4.0 - t };
+    Color::from_rgb([(t - 2.0).clamp(0.0, 1.0), green, (2.0 - t).clamp(0.0, 1.0)].map(|channel| channel * 255.0))
+}
+
+// Color estable para cada material a partir de su nombre (hash FNV-1a)
+fn material_color(name: &str) -> Color {
+    let hash = name.bytes().fold(0x811C_9DC5u32, |hash, byte| (hash ^ byte as u32).wrapping_mul(0x0100_0193));
+    let channel = |shift: u32| 64 + ((hash >> shift) & 0xFF) as u8 / 4 * 3;
+    Color::new(channel(16), channel(8), channel(0))
+}
+
+// Profundidad del rebote más profundo que se traza desde un impacto, con los mismos límites que
+// cast_ray (la ruleta rusa no se tiene en cuenta)
+fn reached_depth(intersect: &Intersect, direction: &Vec3, objects: &[Box<dyn RayIntersect>], settings: &RenderSettings, path: PathState, time: f32) -> u32 {
+    let mut deepest = path.depth;
+    let mut follow = |next_direction: Vec3, next_path: PathState| {
+        if !next_path.within_limits(settings) {
+            return;
+        }
+        let origin = offset_origin(intersect, &next_direction);
+        let next = closest_hit(objects, &Ray::new(origin, next_direction).with_time(time));
+        let reached = if next.is_intersecting {
+            reached_depth(&next, &next_direction, objects, settings, next_path, time)
+        } else {
+            next_path.depth
+        };
+        deepest = deepest.max(reached);
+    };
+
+    let reflectivity = reflectivity(intersect.material);
+    if reflectivity > 0.0 {
+        follow(reflect(direction, &intersect.normal).normalize(), path.reflected(reflectivity));
+    }
+    let transparency = transparency(intersect.material);
+    if transparency > 0.0 {
+        follow(refract(direction, &intersect.normal, intersect.material.refractive_index), path.refracted(transparency));
+    }
+    deepest
+}
+
+// Color de una muestra según el modo de render, junto con el objeto que ve el rayo; las vistas
+// de diagnóstico pintan de negro lo que no choca con nada
+fn shade(ray: &Ray, objects: &[Box<dyn RayIntersect>], scene: &Scene, settings: &RenderSettings, rng: &mut Rng) -> (Color, Option<usize>) {
+    let mut full_shading = || cast_ray(ray, objects, scene, settings, PathState::PRIMARY, rng);
+    let lights = &scene.lights;
+    let diagnostic = |paint: &dyn Fn(&Intersect) -> Color| {
+        pick(objects, ray).map_or((Color::black(), None), |(object, intersect)| (paint(&intersect), Some(object)))
+    };
+    let gray = |value: f32| Color::from_rgb([value.clamp(0.0, 1.0) * 255.0; 3]);
+
+    match settings.mode {
+        RenderMode::Shaded => full_shading(),
+        RenderMode::Heatmap => {
+            let before = stats::intersection_tests();
+            let (_, object) = full_shading();
+            (heat_color((stats::intersection_tests() - before) as f32 / HEATMAP_RANGE), object)
+        }
+        RenderMode::Normals => diagnostic(&|intersect| {
+            Color::from_rgb([intersect.normal.x, intersect.normal.y, intersect.normal.z].map(|n| (n * 0.5 + 0.5) * 255.0))
+        }),
+        RenderMode::Depth => diagnostic(&|intersect| gray(1.0 - intersect.distance / DEPTH_RANGE)),
+        RenderMode::Uv => diagnostic(&|intersect| {
+            let (u, v) = uv_mapping(intersect);
+            Color::from_rgb([u * 255.0, v * 255.0, 0.0])
+        }),
+        RenderMode::MaterialId => diagnostic(&|intersect| material_color(&intersect.material.name)),
+        // Fracción de las luces que llegan al punto
+        RenderMode::Shadow => diagnostic(&|intersect| {
+            let shadow: f32 = lights.iter()
+                .map(|source| cast_shadow(intersect, &source.at(ray.time, settings.day_duration), objects, ray.time))
+                .sum();
+            gray(1.0 - shadow / lights.len().max(1) as f32)
+        }),
+        RenderMode::ReflectionDepth => diagnostic(&|intersect| {
+            // El rojo es el rebote más profundo que permiten los límites
+            let deepest = settings.max_depth.min(settings.max_reflection_depth + settings.max_refraction_depth);
+            let reached = reached_depth(intersect, &ray.direction, objects, settings, PathState::PRIMARY, ray.time);
+            heat_color(reached as f32 / deepest.max(1) as f32)
+        }),
+    }
 }
 
 fn skybox(ray_direction: &Vec3) -> Color {
@@ -189,143 +646,171 @@ fn skybox(ray_direction: &Vec3) -> Color {
 }
 
 fn uv_mapping(intersect: &Intersect) -> (f32, f32) {
-    let u = 0.5 + (intersect.normal.x.atan2(intersect.normal.z)) / (2.0 * PI);
-    let v = 0.5 - (intersect.normal.y.asin()) / PI;
-    (u, v)
+    intersect.uv
 }
 
-fn render(framebuffer: &mut Framebuffer, objects: &[Box<dyn RayIntersect>], camera: &Camera, light: &Light, ambient_light: f32, time: f32) {
+// Devuelve los contadores de rayos y pruebas de intersección del cuadro. El objeto `highlight`,
+// si hay uno, se tiñe para resaltarlo.
+fn render(
+    framebuffer: &mut Framebuffer,
+    objects: &[Box<dyn RayIntersect>],
+    scene: &Scene,
+    camera: &Camera,
+    time: f32,
+    settings: &RenderSettings,
+    highlight: Option<usize>,
+) -> RenderStats {
+    let start = Instant::now();
     let width = framebuffer.width as f32;
     let height = framebuffer.height as f32;
-    let aspect_ratio = width / height;
-    let fov = PI / 4.0;
-    let perspective_scale = (fov * 0.5).tan();
+    let samples = settings.samples(camera.aperture > 0.0 || camera.motion_blur());
+    // Cada semilla usa un bloque distinto de semillas por píxel
+    let seed_offset = settings.seed.wrapping_mul((framebuffer.width * framebuffer.height) as u32);
 
     for y in 0..framebuffer.height {
         for x in 0..framebuffer.width {
-            let screen_x = (2.0 * x as f32) / width - 1.0;
-            let screen_y = -(2.0 * y as f32) / height + 1.0;
-
-            let screen_x = screen_x * aspect_ratio * perspective_scale;
-            let screen_y = screen_y * perspective_scale;
+            // Semilla fija por píxel para que el ruido del desenfoque no parpadee entre cuadros
+            let mut rng = Rng::new(((y * framebuffer.width + x) as u32).wrapping_add(seed_offset));
+            let mut accumulated = [0.0; 3];
 
-            let ray_direction = normalize(&Vec3::new(screen_x, screen_y, -1.0));
-            let rotated_direction = camera.base_change(&ray_direction);
+            for _ in 0..samples {
+                // Con varias muestras cada una cae en un punto distinto del píxel (antialiasing)
+                let (jitter_x, jitter_y) = if samples > 1 { (rng.next_f32() - 0.5, rng.next_f32() - 0.5) } else { (0.0, 0.0) };
+                let sample = match camera.primary_ray(x as f32 + jitter_x, y as f32 + jitter_y, width, height, time, &mut rng) {
+                    Some(ray) => {
+                        stats::record_primary();
+                        let (color, object) = shade(&ray, objects, scene, settings, &mut rng);
+                        if highlight.is_some() && object == highlight {
+                            (color * 0.5 + HIGHLIGHT_COLOR * 0.5).to_rgb()
+                        } else {
+                            color.to_rgb()
+                        }
+                    }
+                    None => Color::black().to_rgb(),
+                };
+                for channel in 0..3 {
+                    accumulated[channel] += sample[channel];
+                }
+            }
 
-            let pixel_color = cast_ray(&camera.eye, &rotated_direction, objects, light, ambient_light, 0, time);
+            let pixel_color = Color::from_rgb(accumulated.map(|channel| channel / samples as f32));
             framebuffer.set_current_color(pixel_color.to_hex());
             framebuffer.point(x, y);
         }
     }
+
+    RenderStats::take(start.elapsed().as_secs_f32())
 }
 
-fn main() {
-    let window_width = 800;
-    let window_height = 600;
-    let framebuffer_width = 800;
-    let framebuffer_height = 600;
-    let frame_delay = Duration::from_millis(16);
+// Renderiza sin ventana cada cuadro del reloj virtual y lo envía al archivo de salida
+// (carpeta de imágenes PPM, GIF, Y4M o RGB crudo según la extensión)
+fn render_timeline(
+    framebuffer: &mut Framebuffer,
+    scene: &Scene,
+    camera: &mut Camera,
+    camera_path: Option<&CameraPath>,
+    timeline: &Timeline,
+    output: &str,
+    settings: &RenderSettings,
+) -> std::io::Result<RenderStats> {
+    let mut writer = video::create(output, framebuffer.width, framebuffer.height, timeline.fps)?;
+    let frames = timeline.frame_count();
+    let mut total = RenderStats::default();
+    let objects = scene.build().map_err(std::io::Error::other)?;
 
-    let mut framebuffer = Framebuffer::new(framebuffer_width, framebuffer_height);
+    for frame in 0..frames {
+        let time = timeline.time_at(frame);
+        if let Some(camera_path) = camera_path {
+            camera_path.apply(camera, time);
+        }
 
-    let mut window = Window::new(
-        "Raytracer with Animated Textures",
-        window_width,
-        window_height,
-        WindowOptions::default(),
-    ).unwrap();
+        total.add(&render(framebuffer, &objects, scene, camera, time, settings, None));
+        writer.write_frame(framebuffer)?;
+        println!("Cuadro {}/{} (t = {:.3} s)", frame + 1, frames, time);
+    }
 
-    let body_material = Material::new(
-        Color::new(100, 100, 255), // Color azul para el cuerpo
-        30.0,
-        [0.6, 0.3, 0.0, 0.0],
-        0.0,
-        "cuerpo"
-    );
-    
-    let head_material = Material::new(
-        Color::new(200, 50, 50), // Color rojo para la cabeza
-        50.0,
-        [0.4, 0.4, 0.2, 0.0],
-        0.0,
-        "cabeza"
-    );
-    
-    let leg_material = Material::new(
-        Color::new(80, 80, 80), // Color gris para las piernas
-        20.0,
-        [0.8, 0.2, 0.0, 0.0],
-        0.0,
-        "piernas"
-    );
-    
-    let arm_material = Material::new(
-        Color::new(80, 100, 80), // Verde oscuro para los brazos
-        10.0,
-        [0.6, 0.3, 0.0, 0.0],
-        0.0,
-        "brazos"
-    );
-    
-    let ground_material = Material::new(
-        Color::new(34, 139, 34), // Color verde para el suelo (césped)
-        10.0,
-        [0.6, 0.2, 0.0, 0.0],
-        0.0,
-        "suelo"
-    );
-    
-    let sun_material = Material::new(
-        Color::new(255, 223, 0), // Color amarillo para el sol
-        50.0,
-        [1.0, 0.5, 0.0, 0.0],
-        0.0,
-        "sol"
-    );
-    
+    writer.finish()?;
+    Ok(total)
+}
 
-    let objects: Vec<Box<dyn RayIntersect>> = vec![
-        Box::new(Sphere {
-            center: Vec3::new(0.0, 1.0, 0.0), 
-            radius: 0.5,
-            material: head_material.clone(),
-        }),
-        Box::new(Cube {
-            min: Vec3::new(-0.5, -1.0, -0.5),
-            max: Vec3::new(0.5, 0.5, 0.5),
-            material: body_material.clone(),
-        }),
-        Box::new(Cube {
-            min: Vec3::new(-0.3, -2.0, -0.3),
-            max: Vec3::new(-0.1, -1.0, 0.1),
-            material: leg_material.clone(),
-        }),
-        Box::new(Cube {
-            min: Vec3::new(0.1, -2.0, -0.3),
-            max: Vec3::new(0.3, -1.0, 0.1),
-            material: leg_material.clone(),
-        }),
-        Box::new(Cube {
-            min: Vec3::new(-1.0, 0.0, -0.3),
-            max: Vec3::new(-0.7, 0.5, 0.3),
-            material: arm_material.clone(),
-        }),
-        Box::new(Cube {
-            min: Vec3::new(0.7, 0.0, -0.3),
-            max: Vec3::new(1.0, 0.5, 0.3),
-            material: arm_material.clone(),
-        }),
-        Box::new(Cube {
-            min: Vec3::new(-10.0, -2.1, -10.0),
-            max: Vec3::new(10.0, -2.0, 10.0),
-            material: ground_material.clone(),
-        }),
-        Box::new(Sphere {
-            center: Vec3::new(5.0, 5.0, -5.0), 
-            radius: 1.0,
-            material: sun_material.clone(),
-        }),
-    ];
+// Ciclo de proyecciones disponibles con la tecla V
+fn next_projection(projection: &Projection) -> Projection {
+    match projection {
+        Projection::Perspective => Projection::Orthographic { height: 8.0 },
+        Projection::Orthographic { .. } => Projection::Fisheye { fov: PI },
+        Projection::Fisheye { .. } => Projection::Equirectangular,
+        Projection::Equirectangular => Projection::Perspective,
+    }
+}
+
+// Ciclo de formas de apertura disponibles con la tecla B
+fn next_bokeh(bokeh: &Bokeh) -> Bokeh {
+    match bokeh {
+        Bokeh::Circle => Bokeh::polygon(6, 0.0),
+        Bokeh::Polygon(_) => Bokeh::custom(star_outline(5, 0.45)),
+        Bokeh::Custom(_) => Bokeh::Circle,
+    }
+}
+
+fn star_outline(points: u32, inner_radius: f32) -> Vec<(f32, f32)> {
+    (0..points * 2)
+        .map(|i| {
+            let radius = if i % 2 == 0 { 1.0 } else { inner_radius };
+            let angle = PI / 2.0 + i as f32 * PI / points as f32;
+            (radius * angle.cos(), radius * angle.sin())
+        })
+        .collect()
+}
+
+// Luz ambiental según la hora del día
+fn ambient_at(time: f32, day_duration: f32) -> f32 {
+    let time_factor = (time.rem_euclid(day_duration)) / day_duration;
+
+    if time_factor < 0.5 {
+        AMBIENT_LIGHT_DAY
+    } else {
+        AMBIENT_LIGHT_NIGHT
+    }
+}
+
+// Campo de visión y aspecto del sensor: los de la línea de comandos pesan más que los de la escena
+fn apply_lens(camera: &mut Camera, scene: &Scene, options: &Options) {
+    camera.fov = options.fov.or(scene.fov).unwrap_or(DEFAULT_FOV);
+    camera.aspect_ratio = options.aspect_ratio.or(scene.aspect_ratio);
+}
+
+// Resumen de un render sin ventana en la consola y, con --stats, también en JSON
+fn report(stats: &RenderStats, stats_file: Option<&str>) {
+    println!("{}", stats.summary());
+    if let Some(stats_file) = stats_file {
+        if let Err(error) = stats.save_json(stats_file) {
+            eprintln!("Error al escribir {}: {}", stats_file, error);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let options = match cli::parse(&args) {
+        Ok(Some(options)) => options,
+        Ok(None) => {
+            println!("{}", cli::help());
+            return;
+        }
+        Err(error) => {
+            eprintln!("{}\nUsa --help para ver las opciones.", error);
+            std::process::exit(2);
+        }
+    };
+    let mut settings = options.settings;
+
+    let window_width = options.width;
+    let window_height = options.height;
+    let frame_delay = Duration::from_secs_f32(1.0 / TARGET_FPS);
+
+    let mut framebuffer = Framebuffer::new(options.width, options.height);
+    framebuffer.set_background_color(0x191970);  // Mismo azul que la parte baja del skybox
 
     let mut camera = Camera::new(
         Vec3::new(0.0, 0.0, 10.0), 
@@ -333,64 +818,434 @@ fn main() {
         Vec3::new(0.0, 1.0, 0.0),  
     );
 
-    let mut light = Light::new(Vec3::new(1.0, -1.0, 5.0), Color::new(255, 255, 255), 1.0);
+    // Sin --scene se usa scene.txt y, si no existe, la escena por defecto incluida en el binario
+    let scene_path = options.scene.clone().unwrap_or_else(|| DEFAULT_SCENE_FILE.to_string());
+    let loaded = if options.scene.is_none() && !std::path::Path::new(&scene_path).exists() {
+        Scene::parse(DEFAULT_SCENE, DEFAULT_SCENE_FILE)
+    } else {
+        Scene::load(&scene_path)
+    };
+    let mut scene = loaded.unwrap_or_else(|error| {
+        eprintln!("No se pudo cargar la escena: {}", error);
+        std::process::exit(1);
+    });
+    let mut objects = scene.build().unwrap_or_else(|error| {
+        eprintln!("No se pudo cargar la escena: {}", error);
+        std::process::exit(1);
+    });
+    apply_lens(&mut camera, &scene, &options);
+
+    let path_file = options.path.clone().unwrap_or_else(|| DEFAULT_PATH_FILE.to_string());
+    let mut camera_path = match CameraPath::load(&path_file) {
+        Ok(camera_path) => camera_path,
+        Err(error) => {
+            // En la ventana el archivo puede no existir todavía: R graba el recorrido en él
+            if options.path.is_some() && options.command != Command::View {
+                eprintln!("No se pudo cargar el recorrido: {}", error);
+                std::process::exit(1);
+            }
+            if std::path::Path::new(&path_file).exists() {
+                eprintln!("No se pudo cargar el recorrido: {}", error);
+            }
+            CameraPath::new(Spline::CatmullRom, PATH_DURATION)
+        }
+    };
+
+    // Sin ventana la cámara sigue el recorrido si tiene puntos de control
+    let follow_path = if camera_path.keys.is_empty() { None } else { Some(&camera_path) };
+    match options.command {
+        Command::View => {}
+        Command::Render => {
+            if let Some(camera_path) = follow_path {
+                camera_path.apply(&mut camera, options.start);
+            }
+            let stats = render(&mut framebuffer, &objects, &scene, &camera, options.start, &settings, None);
+            if let Err(error) = framebuffer.save_ppm(&options.output) {
+                eprintln!("Error al escribir {}: {}", options.output, error);
+                std::process::exit(1);
+            }
+            println!("Imagen guardada en {}", options.output);
+            report(&stats, options.stats.as_deref());
+            return;
+        }
+        Command::Animate => {
+            // Por defecto dura lo mismo que el recorrido indicado con --path o un ciclo de día y noche
+            let duration = options.duration.unwrap_or(match follow_path {
+                Some(camera_path) if options.path.is_some() => camera_path.duration,
+                _ => settings.day_duration,
+            });
+            let timeline = Timeline::new(options.start, duration, options.fps);
+            let stats = match render_timeline(&mut framebuffer, &scene, &mut camera, follow_path, &timeline, &options.output, &settings) {
+                Ok(stats) => stats,
+                Err(error) => {
+                    eprintln!("Error al escribir {}: {}", options.output, error);
+                    std::process::exit(1);
+                }
+            };
+            report(&stats, options.stats.as_deref());
+            return;
+        }
+    }
+
+    let mut window = Window::new(
+        "Raytracer with Animated Textures",
+        window_width,
+        window_height,
+        WindowOptions::default(),
+    ).unwrap();
+
+    let mut playing_path: Option<Instant> = None;
+    let mut recording: Option<Instant> = None;
     let start_time = Instant::now();
     let rotation_speed = PI / 10.0;
+    let mut last_mouse: Option<(f32, f32)> = None;
+    let mut selected: Option<usize> = None;
+    let mut left_was_down = false;
+    let mut dragging: Option<(f32, (f32, f32))> = None;  // Distancia al punto tomado y última posición del mouse
+
+    // La escena se renderiza en `framebuffer`, cuyo tamaño varía, y se amplía a `screen`
+    let mut screen = Framebuffer::new(window_width, window_height);
+    let mut resolution = DynamicResolution::new(TARGET_FPS);
+    let mut last_activity = Instant::now();
+    let mut last_pointer: Option<(f32, f32)> = None;
+    let mut show_hud = true;
+    let mut frame_time = frame_delay.as_secs_f32();
+    let mut scene_modified = modified_time(&scene_path);
+    let mut last_reload_check = Instant::now();
+    let mut scene_error: Option<String> = None;
 
     while window.is_open() && !window.is_key_down(Key::Escape) {
-        let elapsed_time = start_time.elapsed().as_secs_f32();
-        let time_factor = (elapsed_time % DAY_DURATION) / DAY_DURATION;
-
-        let light_angle = time_factor * 2.0 * PI;
-        light.position.x = light_angle.cos() * 10.0;
-        light.position.y = light_angle.sin() * 10.0;
-
-        if time_factor < 0.25 {
-            light.color = Color::new(255, 223, 186);  
-            light.intensity = 1.2;  
-        } else if time_factor < 0.75 {
-            light.color = Color::new(255, 255, 224);
-            light.intensity = 1.5;  
-        } else {
-            light.color = Color::new(255, 140, 0);
-            light.intensity = 1.0;  
+        let frame_start = Instant::now();
+        let elapsed_time = options.start + start_time.elapsed().as_secs_f32();
+
+        // Cualquier tecla, clic, movimiento del mouse o recorrido en curso cuenta como actividad
+        let pointer = window.get_mouse_pos(MouseMode::Pass);
+        let active = !window.get_keys().is_empty()
+            || [MouseButton::Left, MouseButton::Middle, MouseButton::Right].iter().any(|&button| window.get_mouse_down(button))
+            || window.get_scroll_wheel().is_some()
+            || pointer != last_pointer
+            || playing_path.is_some()
+            || recording.is_some();
+        last_pointer = pointer;
+        if active {
+            last_activity = Instant::now();
         }
+        let idle = last_activity.elapsed().as_secs_f32() > IDLE_DELAY;
 
-        let ambient_light = if time_factor < 0.5 {
-            AMBIENT_LIGHT_DAY
-        } else {
-            AMBIENT_LIGHT_NIGHT
-        };
+        // Recarga la escena cuando cambia su archivo, sin tocar la cámara. Si no se puede leer
+        // se sigue mostrando la anterior y el error aparece en pantalla hasta que se corrija.
+        if last_reload_check.elapsed().as_secs_f32() >= RELOAD_INTERVAL {
+            last_reload_check = Instant::now();
+            let modified = modified_time(&scene_path);
+            if modified.is_some() && modified != scene_modified {
+                scene_modified = modified;
+                match Scene::load(&scene_path).and_then(|reloaded| reloaded.build().map(|built| (reloaded, built))) {
+                    Ok((reloaded, built)) => {
+                        // El lente del archivo solo se aplica si cambió, para no pisar el que se
+                        // está usando en la ventana
+                        let lens_changed = (reloaded.fov, reloaded.aspect_ratio) != (scene.fov, scene.aspect_ratio);
+                        scene = reloaded;
+                        objects = built;
+                        if lens_changed {
+                            apply_lens(&mut camera, &scene, &options);
+                        }
+                        selected = selected.filter(|&index| index < scene.objects.len());
+                        dragging = None;
+                        scene_error = None;
+                        println!("Escena recargada de {}", scene_path);
+                    }
+                    Err(error) => {
+                        eprintln!("No se pudo recargar la escena: {}", error);
+                        scene_error = Some(error);
+                    }
+                }
+            }
+        }
 
-        if window.is_key_down(Key::W) {
-            camera.eye += camera.direction() * 0.1;  
+        // P reproduce el recorrido cargado; R graba uno nuevo mientras se maneja la cámara
+        if window.is_key_pressed(Key::P, KeyRepeat::No) && !camera_path.keys.is_empty() {
+            playing_path = match playing_path {
+                Some(_) => None,
+                None => Some(Instant::now()),
+            };
+        }
+        if window.is_key_pressed(Key::R, KeyRepeat::No) {
+            match recording {
+                Some(_) => {
+                    recording = None;
+                    match camera_path.save(&path_file) {
+                        Ok(()) => println!("Recorrido guardado en {} ({} puntos)", path_file, camera_path.keys.len()),
+                        Err(error) => eprintln!("No se pudo guardar {}: {}", path_file, error),
+                    }
+                }
+                None => {
+                    playing_path = None;
+                    camera_path = CameraPath::new(Spline::CatmullRom, 0.0);
+                    camera_path.push(CameraKey::from_camera(&camera));
+                    recording = Some(Instant::now());
+                    println!("Grabando recorrido...");
+                }
+            }
         }
-        if window.is_key_down(Key::S) {
-            camera.eye -= camera.direction() * 0.1;  
+        if let Some(started) = recording {
+            let recorded = started.elapsed().as_secs_f32();
+            if recorded - camera_path.duration >= RECORD_INTERVAL {
+                camera_path.duration = recorded;
+                camera_path.push(CameraKey::from_camera(&camera));
+            }
+        }
+        if let Some(started) = playing_path {
+            camera_path.apply(&mut camera, started.elapsed().as_secs_f32());
         }
 
-        if window.is_key_down(Key::Left) {
-            camera.orbit(rotation_speed, 0.0);
+        if window.is_key_pressed(Key::F, KeyRepeat::No) {
+            camera.toggle_mode();
+            last_mouse = None;
+            println!("Modo de cámara: {}", camera.mode.name());
         }
 
-        if window.is_key_down(Key::Right) {
-            camera.orbit(-rotation_speed, 0.0);
+        if window.is_key_down(Key::Equal) {
+            camera.zoom_by(ZOOM_STEP);
+        }
+        if window.is_key_down(Key::Minus) {
+            camera.zoom_by(1.0 / ZOOM_STEP);
         }
 
-        if window.is_key_down(Key::Up) {
-            camera.orbit(0.0, -rotation_speed);
+        if window.is_key_down(Key::RightBracket) {
+            camera.aperture = (camera.aperture + APERTURE_STEP).min(MAX_APERTURE);
+        }
+        if window.is_key_down(Key::LeftBracket) {
+            camera.aperture = (camera.aperture - APERTURE_STEP).max(0.0);
+        }
+        if window.is_key_pressed(Key::V, KeyRepeat::No) {
+            camera.projection = next_projection(&camera.projection);
+            println!("Proyección: {}", camera.projection.name());
+        }
+        if window.is_key_pressed(Key::M, KeyRepeat::No) {
+            camera.shutter_close = if camera.motion_blur() { camera.shutter_open } else { camera.shutter_open + SHUTTER_TIME };
+            println!("Desenfoque de movimiento: {}", if camera.motion_blur() { "activado" } else { "desactivado" });
+        }
+        if window.is_key_pressed(Key::H, KeyRepeat::No) {
+            show_hud = !show_hud;
         }
 
-        if window.is_key_down(Key::Down) {
-            camera.orbit(0.0, rotation_speed);
+        // Teclas 1 a 8: sombreado normal y vistas de diagnóstico
+        let mode_keys = [Key::Key1, Key::Key2, Key::Key3, Key::Key4, Key::Key5, Key::Key6, Key::Key7, Key::Key8];
+        for (key, mode) in mode_keys.iter().zip(RENDER_MODES) {
+            if window.is_key_pressed(*key, KeyRepeat::No) && settings.mode != mode {
+                settings.mode = mode;
+                println!("Vista: {}", settings.mode.name());
+            }
+        }
+        if window.is_key_pressed(Key::B, KeyRepeat::No) {
+            camera.bokeh = next_bokeh(&camera.bokeh);
+            println!("Bokeh: {}", camera.bokeh.name());
         }
 
-        render(&mut framebuffer, &objects, &camera, &light, ambient_light, elapsed_time);
+        // Clic izquierdo: describe el objeto bajo el cursor en la consola y lo resalta;
+        // arrastrando se mueve en el plano de la pantalla
+        let left_down = window.get_mouse_down(MouseButton::Left);
+        if left_down && !left_was_down {
+            if let Some((mouse_x, mouse_y)) = window.get_mouse_pos(MouseMode::Discard) {
+                if let Some(ray) = camera.pinhole_ray(mouse_x, mouse_y, window_width as f32, window_height as f32) {
+                    selected = match pick(&objects, &ray) {
+                        Some((index, intersect)) => {
+                            println!("{}", describe_pick(index, &intersect));
+                            dragging = Some((intersect.distance, (mouse_x, mouse_y)));
+                            Some(index)
+                        }
+                        None => {
+                            println!("Nada bajo el cursor");
+                            None
+                        }
+                    };
+                }
+            }
+        }
+        if !left_down {
+            dragging = None;
+        }
+        left_was_down = left_down;
+
+        // Con un objeto seleccionado: J/L, K/I y O/U lo mueven a lo largo de los ejes de la
+        // cámara, coma y punto lo escalan, N cambia su material, C lo duplica y Supr lo borra
+        if let Some(index) = selected {
+            let key_axis = |positive: Key, negative: Key| {
+                window.is_key_down(positive) as i32 as f32 - window.is_key_down(negative) as i32 as f32
+            };
+            let (right, up, forward) = camera.basis();
+            let mut edited = false;
+
+            let mut movement = (right * key_axis(Key::L, Key::J) + up * key_axis(Key::I, Key::K) + forward * key_axis(Key::U, Key::O)) * MOVE_STEP;
+            if let (Some((distance, (last_x, last_y))), Some((mouse_x, mouse_y))) = (dragging, window.get_mouse_pos(MouseMode::Pass)) {
+                // Tamaño de un píxel a la distancia del punto tomado
+                let pixel = camera.pixel_size(distance, window_height as f32);
+                movement += (right * (mouse_x - last_x) - up * (mouse_y - last_y)) * pixel;
+                dragging = Some((distance, (mouse_x, mouse_y)));
+            }
+            if movement != Vec3::zeros() {
+                scene.objects[index].translation += movement;
+                edited = true;
+            }
+
+            let growth = key_axis(Key::Period, Key::Comma);
+            if growth != 0.0 {
+                scene.objects[index].scale_by(SCALE_STEP.powf(growth));
+                edited = true;
+            }
+
+            if window.is_key_pressed(Key::N, KeyRepeat::No) {
+                let shape = &mut scene.objects[index].shape;
+                let current = scene.materials.iter().position(|material| material.name == shape.material()).unwrap_or(0);
+                let next = &scene.materials[(current + 1) % scene.materials.len()].name;
+                shape.set_material(next);
+                println!("Material: {}", next);
+                edited = true;
+            }
+
+            if window.is_key_pressed(Key::C, KeyRepeat::No) {
+                let mut copy = scene.objects[index].clone();
+                copy.translation += right * DUPLICATE_OFFSET;
+                scene.objects.push(copy);
+                selected = Some(scene.objects.len() - 1);
+                println!("Objeto {} duplicado como {}", index, scene.objects.len() - 1);
+                edited = true;
+            } else if window.is_key_pressed(Key::Delete, KeyRepeat::No) || window.is_key_pressed(Key::Backspace, KeyRepeat::No) {
+                scene.objects.remove(index);
+                selected = None;
+                dragging = None;
+                println!("Objeto {} eliminado", index);
+                edited = true;
+            }
+
+            if edited {
+                match scene.build() {
+                    Ok(built) => objects = built,
+                    Err(error) => eprintln!("No se pudo aplicar el cambio: {}", error),
+                }
+            }
+        }
+
+        // F2: guarda la escena con los cambios en un archivo aparte, porque al escribirla de
+        // nuevo se pierden los comentarios y el orden del archivo original
+        if window.is_key_pressed(Key::F2, KeyRepeat::No) {
+            let edited_path = edited_scene_path(&scene_path);
+            match scene.save(&edited_path) {
+                Ok(()) => println!("Escena guardada en {} ({} objetos)", edited_path, scene.objects.len()),
+                Err(error) => eprintln!("No se pudo guardar {}: {}", edited_path, error),
+            }
+        }
+
+        // Clic derecho: enfoca el objeto bajo el cursor
+        if window.get_mouse_down(MouseButton::Right) {
+            if let Some((mouse_x, mouse_y)) = window.get_mouse_pos(MouseMode::Discard) {
+                if let Some(ray) = camera.pinhole_ray(mouse_x, mouse_y, window_width as f32, window_height as f32) {
+                    let hit = closest_hit(&objects, &ray);
+                    if hit.is_intersecting {
+                        camera.focus_on(&ray.direction, hit.distance);
+                    }
+                }
+            }
+        }
+
+        match camera.mode {
+            CameraMode::Orbit => {
+                if window.is_key_down(Key::W) {
+                    camera.eye += camera.direction() * 0.1;
+                }
+                if window.is_key_down(Key::S) {
+                    camera.eye -= camera.direction() * 0.1;
+                }
 
+                if window.is_key_down(Key::Left) {
+                    camera.orbit(rotation_speed, 0.0);
+                }
+
+                if window.is_key_down(Key::Right) {
+                    camera.orbit(-rotation_speed, 0.0);
+                }
+
+                if window.is_key_down(Key::Up) {
+                    camera.orbit(0.0, -rotation_speed);
+                }
+
+                if window.is_key_down(Key::Down) {
+                    camera.orbit(0.0, rotation_speed);
+                }
+            }
+            CameraMode::FreeFly => {
+                let axis = |positive: Key, negative: Key| {
+                    window.is_key_down(positive) as i32 as f32 - window.is_key_down(negative) as i32 as f32
+                };
+                camera.fly(axis(Key::W, Key::S), axis(Key::D, Key::A), axis(Key::E, Key::Q));
+                camera.look(axis(Key::Right, Key::Left) * LOOK_SPEED, axis(Key::Up, Key::Down) * LOOK_SPEED);
+
+                // Mientras se arrastra un objeto el mouse no mueve la vista
+                if dragging.is_some() {
+                    last_mouse = None;
+                } else if let Some((mouse_x, mouse_y)) = window.get_mouse_pos(MouseMode::Pass) {
+                    if let Some((last_x, last_y)) = last_mouse {
+                        camera.look((mouse_x - last_x) * MOUSE_SENSITIVITY, (last_y - mouse_y) * MOUSE_SENSITIVITY);
+                    }
+                    last_mouse = Some((mouse_x, mouse_y));
+                }
+
+                if let Some((_, scroll)) = window.get_scroll_wheel() {
+                    camera.move_speed = (camera.move_speed * 1.1f32.powf(scroll)).clamp(MIN_MOVE_SPEED, MAX_MOVE_SPEED);
+                }
+            }
+        }
+
+        if idle {
+            resolution.reset();
+        }
+        let (render_width, render_height) = resolution.size(window_width, window_height);
+        framebuffer.resize(render_width, render_height);
+
+        // Los haces de luz en la niebla se trazan solo con la imagen quieta: cada paso es un
+        // rayo de sombra por luz y no dejarían mantener los cuadros por segundo al moverse
+        let frame_settings = RenderSettings { fog_steps: if idle { settings.fog_steps } else { 0 }, ..settings };
+        framebuffer.clear();
+        let frame_stats = render(&mut framebuffer, &objects, &scene, &camera, elapsed_time, &frame_settings, selected);
+        if !idle {
+            resolution.update(frame_stats.total_time());
+        }
+
+        framebuffer.upscale_into(&mut screen);
+        if show_hud {
+            let path_state = if recording.is_some() {
+                " | grabando"
+            } else if playing_path.is_some() {
+                " | recorrido"
+            } else {
+                ""
+            };
+            let mut lines = vec![
+                format!("{:.1} fps  {:.1} ms/cuadro", 1.0 / frame_time, frame_time * 1000.0),
+                format!("render {}x{} ({:.0}%)  {:.2} M rayos/s", render_width, render_height, resolution.scale * 100.0, frame_stats.rays_per_second() / 1e6),
+                format!("hora {}  t = {:.1} s", hud::clock(elapsed_time, settings.day_duration), elapsed_time),
+                format!("ojo    {:7.2} {:7.2} {:7.2}", camera.eye.x, camera.eye.y, camera.eye.z),
+                format!("centro {:7.2} {:7.2} {:7.2}", camera.center.x, camera.center.y, camera.center.z),
+                format!("{} | {} | fov {:.0}{}", camera.mode.name(), camera.projection.name(), camera.effective_fov().to_degrees(), path_state),
+                format!("lente {:.2} {} | blur {}", camera.aperture, camera.bokeh.name(), if camera.motion_blur() { "on" } else { "off" }),
+                format!("vista {}", settings.mode.name()),
+            ];
+            if let Some(index) = selected {
+                let shape = &scene.objects[index].shape;
+                lines.push(format!("objeto {} {} | material {}", index, shape.name(), shape.material()));
+            }
+            hud::draw(&mut screen, &lines);
+        }
+        if let Some(error) = &scene_error {
+            hud::error(&mut screen, error);
+        }
         window
-            .update_with_buffer(&framebuffer.buffer, framebuffer_width, framebuffer_height)
+            .update_with_buffer(&screen.buffer, window_width, window_height)
             .unwrap();
 
-        std::thread::sleep(frame_delay);
+        if let Some(remaining) = frame_delay.checked_sub(frame_start.elapsed()) {
+            std::thread::sleep(remaining);
+        }
+        frame_time = frame_start.elapsed().as_secs_f32();
     }
 }
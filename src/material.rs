@@ -1,10 +1,68 @@
 use crate::color::Color;
+
+const MIN_TRANSMISSION: f32 = 1.0 / 255.0;  // Un canal en cero absorbería todo desde el primer tramo
+
+// Absorción de Beer-Lambert dentro de un material transparente. `color` es el que toma la luz
+// blanca después de recorrer una unidad de distancia con densidad 1.
+#[derive(Clone, Copy)]
+pub struct Absorption {
+    pub color: Color,
+    pub density: f32,
+}
+
+impl Absorption {
+    pub const NONE: Absorption = Absorption { color: Color::new(255, 255, 255), density: 0.0 };
+
+    // Fracción de cada canal que sobrevive después de recorrer `distance` dentro del material
+    pub fn transmittance(&self, distance: f32) -> [f32; 3] {
+        self.color.to_rgb().map(|channel| (channel / 255.0).max(MIN_TRANSMISSION).powf(self.density * distance))
+    }
+}
+
+// Dispersión bajo la superficie: la luz entra, se difunde y sale a cierta distancia del punto
+// donde entró. `radius` es hasta dónde llega la mayor parte y `color` la fracción de ese radio que
+// alcanza cada canal; en la piel el rojo llega más lejos.
+#[derive(Clone, Copy)]
+pub struct Subsurface {
+    pub radius: f32,
+    pub color: Color,
+}
+
+impl Subsurface {
+    pub fn radii(&self) -> [f32; 3] {
+        self.color.to_rgb().map(|channel| self.radius * (channel / 255.0).max(MIN_TRANSMISSION))
+    }
+
+    // Perfil de Christensen-Burley por canal, integrado en cada anillo: densidad de la luz que
+    // sale a `distance` del punto donde entró. El lóbulo largo llega hasta el radio del canal.
+    pub fn profile(&self, distance: f32) -> [f32; 3] {
+        self.radii().map(|radius| burley(distance, radius / 3.0))
+    }
+
+    // Distancia al azar con la densidad del canal que llega más lejos, a partir de dos números
+    // uniformes en [0, 1). Devuelve también su densidad.
+    pub fn sample_distance(&self, lobe: f32, u: f32) -> (f32, f32) {
+        let d = self.radii().into_iter().fold(0.0, f32::max) / 3.0;
+        let scale = if lobe < 0.25 { d } else { 3.0 * d };
+        let distance = -scale * (1.0 - u).ln();
+        (distance, burley(distance, d))
+    }
+}
+
+// Suma de dos exponenciales, una corta y otra tres veces más larga, normalizada para que su
+// integral sobre todas las distancias valga 1
+fn burley(distance: f32, d: f32) -> f32 {
+    ((-distance / d).exp() + (-distance / (3.0 * d)).exp()) / (4.0 * d)
+}
+
 #[derive(Clone)]  // Asegúrate de que `Material` implemente Clone si aún no lo has hecho
 pub struct Material {
     pub color: Color,
     pub shininess: f32,
     pub albedo: [f32; 4],
     pub refractive_index: f32,
+    pub absorption: Absorption,
+    pub subsurface: Option<Subsurface>,
     pub name: String,  // Nuevo campo para el nombre del material
 }
 
@@ -15,10 +73,22 @@ impl Material {
             shininess,
             albedo,
             refractive_index,
+            absorption: Absorption::NONE,
+            subsurface: None,
             name: name.to_string(),  // Asignar el nombre al material
         }
     }
 
+    pub fn with_absorption(mut self, color: Color, density: f32) -> Self {
+        self.absorption = Absorption { color, density };
+        self
+    }
+
+    pub fn with_subsurface(mut self, radius: f32, color: Color) -> Self {
+        self.subsurface = Some(Subsurface { radius, color });
+        self
+    }
+
     pub fn black() -> Material {
         Material::new(Color::black(), 0.0, [0.0, 0.0, 0.0, 0.0], 0.0, "black")
     }
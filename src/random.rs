@@ -0,0 +1,30 @@
+// Generador xorshift32: rápido, reproducible y sin dependencias externas
+pub struct Rng {
+    state: u32,
+}
+
+impl Rng {
+    pub fn new(seed: u32) -> Self {
+        // Mezclamos la semilla para que semillas consecutivas (p. ej. índices de píxel)
+        // no produzcan secuencias parecidas; el estado nunca puede ser cero.
+        let mut state = seed.wrapping_mul(0x9E37_79B9) ^ 0x85EB_CA6B;
+        state ^= state >> 16;
+        state = state.wrapping_mul(0x7FEB_352D);
+        state ^= state >> 15;
+        Rng { state: state.max(1) }
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        let mut x = self.state;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.state = x;
+        x
+    }
+
+    // Número uniforme en [0, 1)
+    pub fn next_f32(&mut self) -> f32 {
+        (self.next_u32() >> 8) as f32 / (1u32 << 24) as f32
+    }
+}
@@ -0,0 +1,40 @@
+use nalgebra_glm::Vec3;
+
+// Rayo con el intervalo de distancias (t_min, t_max) en el que se aceptan intersecciones
+#[derive(Clone, Copy)]
+pub struct Ray {
+    pub origin: Vec3,
+    pub direction: Vec3,
+    pub t_min: f32,
+    pub t_max: f32,
+    pub time: f32,  // Instante en que viaja el rayo, para evaluar objetos en movimiento
+}
+
+impl Ray {
+    pub fn new(origin: Vec3, direction: Vec3) -> Self {
+        Ray::with_range(origin, direction, 0.0, f32::INFINITY)
+    }
+
+    pub fn with_range(origin: Vec3, direction: Vec3, t_min: f32, t_max: f32) -> Self {
+        Ray {
+            origin,
+            direction,
+            t_min,
+            t_max,
+            time: 0.0,
+        }
+    }
+
+    pub fn with_time(mut self, time: f32) -> Self {
+        self.time = time;
+        self
+    }
+
+    pub fn at(&self, t: f32) -> Vec3 {
+        self.origin + self.direction * t
+    }
+
+    pub fn contains(&self, t: f32) -> bool {
+        t > self.t_min && t < self.t_max
+    }
+}
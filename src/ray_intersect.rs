@@ -1,32 +1,52 @@
 use nalgebra_glm::Vec3;
-use crate::material::Material;
+use std::f32::consts::PI;
+use crate::color::Color;
+use crate::material::{Absorption, Material};
+use crate::ray::Ray;
 
-pub struct Intersect {
+// Material que acompaña a los rayos sin intersección; nunca se usa para sombrear
+static NO_MATERIAL: Material = Material {
+    color: Color::black(),
+    shininess: 0.0,
+    albedo: [0.0, 0.0, 0.0, 0.0],
+    refractive_index: 0.0,
+    absorption: Absorption::NONE,
+    subsurface: None,
+    name: String::new(),
+};
+
+// El material se toma prestado del objeto intersectado en lugar de clonarlo, así
+// que probar un rayo contra la escena no hace ninguna reserva de memoria.
+pub struct Intersect<'a> {
     pub is_intersecting: bool,
     pub distance: f32,
     pub point: Vec3,
     pub normal: Vec3,
-    pub material: Material,
+    pub uv: (f32, f32),
+    pub material: &'a Material,
 }
 
-impl Intersect {
+impl<'a> Intersect<'a> {
     pub fn empty() -> Self {
         Intersect {
             is_intersecting: false,
             distance: f32::INFINITY,
             point: Vec3::new(0.0, 0.0, 0.0),
             normal: Vec3::new(0.0, 0.0, 0.0),
-            material: Material::default(),
+            uv: (0.0, 0.0),
+            material: &NO_MATERIAL,
         }
     }
 
-    pub fn hit(distance: f32, point: Vec3, normal: Vec3, material: &Material) -> Self {
+    // Cada primitiva pasa las coordenadas de textura de su propia parametrización
+    pub fn hit(distance: f32, point: Vec3, normal: Vec3, uv: (f32, f32), material: &'a Material) -> Self {
         Intersect {
             is_intersecting: true,
             distance,
             point,
             normal,
-            material: material.clone(),
+            uv,
+            material,
         }
     }
 
@@ -35,6 +55,23 @@ impl Intersect {
     }
 }
 
+// Mapeo esférico a partir de la normal, para la esfera y el cubo
+pub fn spherical_uv(normal: &Vec3) -> (f32, f32) {
+    let u = 0.5 + (normal.x.atan2(normal.z)) / (2.0 * PI);
+    let v = 0.5 - (normal.y.clamp(-1.0, 1.0).asin()) / PI;
+    (u, v)
+}
+
 pub trait RayIntersect {
-    fn ray_intersect(&self, origin: &Vec3, direction: &Vec3) -> Intersect;
+    // Intersección más cercana dentro del intervalo (t_min, t_max) del rayo
+    fn ray_intersect(&self, ray: &Ray) -> Intersect<'_>;
+
+    // Todos los tramos (entrada, salida) en que la recta del rayo está dentro del sólido,
+    // ordenados y sin filtrar por el intervalo del rayo. Lo usa la geometría CSG.
+    fn intervals(&self, ray: &Ray) -> Vec<(Intersect<'_>, Intersect<'_>)>;
+
+    // Consulta de sombra: basta con saber si algo bloquea el intervalo del rayo
+    fn occluded(&self, ray: &Ray) -> bool {
+        self.ray_intersect(ray).is_intersecting
+    }
 }
\ No newline at end of file
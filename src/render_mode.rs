@@ -0,0 +1,49 @@
+// Vistas de diagnóstico que reemplazan el sombreado normal
+#[derive(Clone, Copy, PartialEq)]
+pub enum RenderMode {
+    Shaded,           // Sombreado completo
+    Normals,          // Normal de la superficie como color
+    Depth,            // Distancia al primer impacto: blanco cerca, negro lejos
+    Uv,               // Coordenadas (u, v) en rojo y verde
+    MaterialId,       // Un color fijo por material
+    Shadow,           // Solo el término de sombra: blanco iluminado, negro en sombra
+    ReflectionDepth,  // Rebote más profundo alcanzado, de azul (ninguno) a rojo (el máximo)
+    Heatmap,          // Pruebas de intersección por píxel
+}
+
+// En el orden de las teclas 1 a 8
+pub const RENDER_MODES: [RenderMode; 8] = [
+    RenderMode::Shaded,
+    RenderMode::Normals,
+    RenderMode::Depth,
+    RenderMode::Uv,
+    RenderMode::MaterialId,
+    RenderMode::Shadow,
+    RenderMode::ReflectionDepth,
+    RenderMode::Heatmap,
+];
+
+impl RenderMode {
+    pub fn name(&self) -> &'static str {
+        match self {
+            RenderMode::Shaded => "shaded",
+            RenderMode::Normals => "normals",
+            RenderMode::Depth => "depth",
+            RenderMode::Uv => "uv",
+            RenderMode::MaterialId => "material",
+            RenderMode::Shadow => "shadow",
+            RenderMode::ReflectionDepth => "reflection-depth",
+            RenderMode::Heatmap => "heatmap",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Result<RenderMode, String> {
+        RENDER_MODES.iter()
+            .find(|mode| mode.name() == name)
+            .copied()
+            .ok_or_else(|| {
+                let names: Vec<&str> = RENDER_MODES.iter().map(|mode| mode.name()).collect();
+                format!("modo desconocido: {} (disponibles: {})", name, names.join(", "))
+            })
+    }
+}
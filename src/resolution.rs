@@ -0,0 +1,38 @@
+const MIN_SCALE: f32 = 0.25;  // Nunca bajamos de un cuarto de la resolución por lado
+const SCALE_STEPS: f32 = 20.0;  // La escala se redondea a pasos de 5% para no cambiar de tamaño en cada cuadro
+const GROWTH: f32 = 0.25;  // Fracción de la subida deseada que se aplica por cuadro
+
+// Resolución dinámica: mide lo que tarda cada render y ajusta la escala de la imagen
+// interna para acercarse al tiempo objetivo por cuadro.
+pub struct DynamicResolution {
+    pub scale: f32,
+    target_frame_time: f32,
+}
+
+impl DynamicResolution {
+    pub fn new(target_fps: f32) -> Self {
+        DynamicResolution {
+            scale: 1.0,
+            target_frame_time: 1.0 / target_fps,
+        }
+    }
+
+    pub fn update(&mut self, render_time: f32) {
+        // El costo crece con la cantidad de píxeles, es decir, con el cuadrado de la escala
+        let ideal = self.scale * (self.target_frame_time / render_time.max(1e-4)).sqrt();
+
+        // Bajamos de golpe si vamos lentos y subimos poco a poco para no oscilar
+        let next = if ideal < self.scale { ideal } else { self.scale + (ideal - self.scale) * GROWTH };
+        self.scale = ((next * SCALE_STEPS).floor() / SCALE_STEPS).clamp(MIN_SCALE, 1.0);
+    }
+
+    // Vuelve a la resolución completa, p. ej. cuando nadie está moviendo la cámara
+    pub fn reset(&mut self) {
+        self.scale = 1.0;
+    }
+
+    pub fn size(&self, width: usize, height: usize) -> (usize, usize) {
+        let scaled = |size: usize| ((size as f32 * self.scale).round() as usize).max(1);
+        (scaled(width), scaled(height))
+    }
+}
@@ -0,0 +1,674 @@
+use nalgebra_glm::{self as glm, Mat4, Vec3};
+use std::fs;
+use std::sync::Arc;
+use crate::color::Color;
+use crate::material::Material;
+use crate::light::Light;
+use crate::fog::Fog;
+use crate::ray_intersect::RayIntersect;
+use crate::sphere::Sphere;
+use crate::cube::Cube;
+use crate::cylinder::Cylinder;
+use crate::cone::Cone;
+use crate::torus::Torus;
+use crate::capsule::Capsule;
+use crate::csg::{Csg, CsgOp};
+use crate::instance::{self, Instance};
+
+const MIN_SCALE: f32 = 1e-3;  // Escala mínima por eje al encoger un objeto desde la ventana
+const SINGULAR_TRANSFORM: &str = "la transformación no tiene inversa (escala demasiado chica o demasiado grande)";
+
+// Descripción editable de una figura; las hojas guardan el nombre de su material
+#[derive(Clone)]
+pub enum Shape {
+    Sphere { material: String, center: Vec3, radius: f32 },
+    Cube { material: String, min: Vec3, max: Vec3 },
+    Cylinder { material: String, base: Vec3, radius: f32, height: f32 },
+    Cone { material: String, base: Vec3, radius: f32, height: f32 },
+    Torus { material: String, center: Vec3, major_radius: f32, minor_radius: f32 },
+    Capsule { material: String, start: Vec3, end: Vec3, radius: f32 },
+    Csg { op: CsgOp, left: Box<Shape>, right: Box<Shape> },
+}
+
+// Balanceo periódico alrededor del eje Z local, como el brazo que saluda
+#[derive(Clone, Copy)]
+pub struct Swing {
+    pub amplitude: f32,  // Grados
+    pub speed: f32,      // Radianes por segundo
+}
+
+#[derive(Clone)]
+pub struct SceneObject {
+    pub shape: Shape,
+    pub translation: Vec3,
+    pub rotation: Vec3,  // Grados alrededor de X, luego Y, luego Z
+    pub scale: Vec3,
+    pub swing: Option<Swing>,
+}
+
+// Fuente de luz de la escena; el sol sigue el ciclo de día y noche
+#[derive(Clone)]
+pub enum LightSource {
+    Sun,
+    Point(Light),
+}
+
+pub struct Scene {
+    pub materials: Vec<Material>,
+    pub lights: Vec<LightSource>,
+    pub fog: Option<Fog>,
+    pub fov: Option<f32>,           // Campo de visión vertical en radianes
+    pub aspect_ratio: Option<f32>,  // Relación de aspecto del sensor
+    pub objects: Vec<SceneObject>,
+}
+
+impl LightSource {
+    // La luz que emite en el instante `time`
+    pub fn at(&self, time: f32, day_duration: f32) -> Light {
+        match self {
+            LightSource::Sun => Light::sun_at(time, day_duration),
+            LightSource::Point(light) => light.clone(),
+        }
+    }
+}
+
+impl Shape {
+    fn build(&self, materials: &[Material]) -> Box<dyn RayIntersect> {
+        let material = |name: &String| materials.iter().find(|material| &material.name == name).cloned().unwrap_or_default();
+
+        match self {
+            Shape::Sphere { material: name, center, radius } => {
+                Box::new(Sphere { center: *center, radius: *radius, material: material(name) })
+            }
+            Shape::Cube { material: name, min, max } => {
+                Box::new(Cube { min: *min, max: *max, material: material(name) })
+            }
+            Shape::Cylinder { material: name, base, radius, height } => {
+                Box::new(Cylinder { base: *base, radius: *radius, height: *height, material: material(name) })
+            }
+            Shape::Cone { material: name, base, radius, height } => {
+                Box::new(Cone { base: *base, radius: *radius, height: *height, material: material(name) })
+            }
+            Shape::Torus { material: name, center, major_radius, minor_radius } => {
+                Box::new(Torus { center: *center, major_radius: *major_radius, minor_radius: *minor_radius, material: material(name) })
+            }
+            Shape::Capsule { material: name, start, end, radius } => {
+                Box::new(Capsule { start: *start, end: *end, radius: *radius, material: material(name) })
+            }
+            Shape::Csg { op, left, right } => Box::new(Csg::new(*op, left.build(materials), right.build(materials))),
+        }
+    }
+
+    // Material de la primera hoja, el que se muestra al seleccionar el objeto
+    pub fn material(&self) -> &str {
+        match self {
+            Shape::Sphere { material, .. }
+            | Shape::Cube { material, .. }
+            | Shape::Cylinder { material, .. }
+            | Shape::Cone { material, .. }
+            | Shape::Torus { material, .. }
+            | Shape::Capsule { material, .. } => material,
+            Shape::Csg { left, .. } => left.material(),
+        }
+    }
+
+    // En una operación CSG se cambia el material de todas las hojas
+    pub fn set_material(&mut self, name: &str) {
+        match self {
+            Shape::Sphere { material, .. }
+            | Shape::Cube { material, .. }
+            | Shape::Cylinder { material, .. }
+            | Shape::Cone { material, .. }
+            | Shape::Torus { material, .. }
+            | Shape::Capsule { material, .. } => *material = name.to_string(),
+            Shape::Csg { left, right, .. } => {
+                left.set_material(name);
+                right.set_material(name);
+            }
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Shape::Sphere { .. } => "sphere",
+            Shape::Cube { .. } => "cube",
+            Shape::Cylinder { .. } => "cylinder",
+            Shape::Cone { .. } => "cone",
+            Shape::Torus { .. } => "torus",
+            Shape::Capsule { .. } => "capsule",
+            Shape::Csg { op: CsgOp::Union, .. } => "union",
+            Shape::Csg { op: CsgOp::Intersection, .. } => "intersection",
+            Shape::Csg { op: CsgOp::Difference, .. } => "difference",
+        }
+    }
+
+    // Centro aproximado en coordenadas locales, usado como pivote al escalar
+    fn center(&self) -> Vec3 {
+        match self {
+            Shape::Sphere { center, .. } | Shape::Torus { center, .. } => *center,
+            Shape::Cube { min, max, .. } => (min + max) * 0.5,
+            Shape::Cylinder { base, height, .. } | Shape::Cone { base, height, .. } => base + Vec3::new(0.0, height * 0.5, 0.0),
+            Shape::Capsule { start, end, .. } => (start + end) * 0.5,
+            Shape::Csg { left, right, .. } => (left.center() + right.center()) * 0.5,
+        }
+    }
+
+    fn materials(&self) -> Vec<&str> {
+        match self {
+            Shape::Csg { left, right, .. } => {
+                let mut names = left.materials();
+                names.extend(right.materials());
+                names
+            }
+            leaf => vec![leaf.material()],
+        }
+    }
+
+    fn write(&self, text: &mut String, indent: usize) {
+        let v = |v: &Vec3| format!("{} {} {}", v.x, v.y, v.z);
+        let arguments = match self {
+            Shape::Sphere { material, center, radius } => format!(" {} {} {}", material, v(center), radius),
+            Shape::Cube { material, min, max } => format!(" {} {} {}", material, v(min), v(max)),
+            Shape::Cylinder { material, base, radius, height } | Shape::Cone { material, base, radius, height } => {
+                format!(" {} {} {} {}", material, v(base), radius, height)
+            }
+            Shape::Torus { material, center, major_radius, minor_radius } => {
+                format!(" {} {} {} {}", material, v(center), major_radius, minor_radius)
+            }
+            Shape::Capsule { material, start, end, radius } => format!(" {} {} {} {}", material, v(start), v(end), radius),
+            Shape::Csg { .. } => String::new(),
+        };
+        text.push_str(&format!("{}{}{}\n", "  ".repeat(indent), self.name(), arguments));
+
+        if let Shape::Csg { left, right, .. } = self {
+            left.write(text, indent + 1);
+            right.write(text, indent + 1);
+        }
+    }
+}
+
+impl SceneObject {
+    pub fn new(shape: Shape) -> Self {
+        SceneObject {
+            shape,
+            translation: Vec3::zeros(),
+            rotation: Vec3::zeros(),
+            scale: Vec3::new(1.0, 1.0, 1.0),
+            swing: None,
+        }
+    }
+
+    fn is_identity(&self) -> bool {
+        self.translation == Vec3::zeros() && self.rotation == Vec3::zeros() && self.scale == Vec3::new(1.0, 1.0, 1.0)
+    }
+
+    // Traslación * rotación * escala, con el balanceo sumado al giro en Z
+    fn transform(&self, swing_angle: f32) -> Mat4 {
+        let rotation = glm::rotation((self.rotation.z + swing_angle).to_radians(), &Vec3::new(0.0, 0.0, 1.0))
+            * glm::rotation(self.rotation.y.to_radians(), &Vec3::new(0.0, 1.0, 0.0))
+            * glm::rotation(self.rotation.x.to_radians(), &Vec3::new(1.0, 0.0, 0.0));
+        glm::translation(&self.translation) * rotation * glm::scaling(&self.scale)
+    }
+
+    // Solo los objetos que se mueven o transforman se envuelven en una instancia
+    fn build(&self, materials: &[Material]) -> Result<Box<dyn RayIntersect>, String> {
+        let shape = self.shape.build(materials);
+        let instance = match self.swing {
+            Some(swing) => {
+                let object = self.clone();
+                Instance::animated(Arc::from(shape), move |time| {
+                    object.transform(swing.amplitude * (1.0 + (time * swing.speed).sin()))
+                })
+            }
+            None if self.is_identity() => return Ok(shape),
+            None => Instance::new(Arc::from(shape), self.transform(0.0)),
+        };
+        match instance {
+            Some(instance) => Ok(Box::new(instance)),
+            None => Err(SINGULAR_TRANSFORM.to_string()),
+        }
+    }
+
+    // Escala alrededor del centro de la figura para que no se desplace hacia el origen
+    pub fn scale_by(&mut self, factor: f32) {
+        let center = self.shape.center();
+        let pivot = |object: &SceneObject| (object.transform(0.0) * glm::vec4(center.x, center.y, center.z, 1.0)).xyz();
+        if (self.scale * factor).abs().min() < MIN_SCALE {
+            return;
+        }
+        let before = pivot(self);
+        self.scale *= factor;
+        self.translation += before - pivot(self);
+    }
+
+    fn write(&self, text: &mut String) {
+        self.shape.write(text, 0);
+        let v = |v: &Vec3| format!("{} {} {}", v.x, v.y, v.z);
+        if self.translation != Vec3::zeros() {
+            text.push_str(&format!("  translate {}\n", v(&self.translation)));
+        }
+        if self.rotation != Vec3::zeros() {
+            text.push_str(&format!("  rotate {}\n", v(&self.rotation)));
+        }
+        if self.scale != Vec3::new(1.0, 1.0, 1.0) {
+            text.push_str(&format!("  scale {}\n", v(&self.scale)));
+        }
+        if let Some(swing) = self.swing {
+            text.push_str(&format!("  swing {} {}\n", swing.amplitude, swing.speed));
+        }
+    }
+}
+
+// Lee exactamente `count` números
+fn numbers(words: &[&str], count: usize) -> Result<Vec<f32>, String> {
+    if words.len() != count {
+        return Err(format!("se esperaban {} números y hay {}", count, words.len()));
+    }
+    words.iter()
+        .map(|word| word.parse::<f32>().ok().filter(|value| value.is_finite()).ok_or_else(|| format!("número inválido: {}", word)))
+        .collect()
+}
+
+fn vec3(values: &[f32]) -> Vec3 {
+    Vec3::new(values[0], values[1], values[2])
+}
+
+// Lee una figura a partir de la línea `*index`; las operaciones CSG consumen las dos siguientes
+fn parse_shape(lines: &[(usize, Vec<&str>)], index: &mut usize) -> Result<Shape, (usize, String)> {
+    let (number, words) = match lines.get(*index) {
+        Some(line) => line,
+        None => return Err((lines.last().map_or(0, |line| line.0), "falta un operando de la operación CSG".to_string())),
+    };
+    *index += 1;
+    let error = |message: String| (*number, message);
+
+    let op = match words[0] {
+        "union" => Some(CsgOp::Union),
+        "intersection" => Some(CsgOp::Intersection),
+        "difference" => Some(CsgOp::Difference),
+        _ => None,
+    };
+    if let Some(op) = op {
+        if words.len() > 1 {
+            return Err(error(format!("{} no lleva argumentos; sus operandos van en las dos líneas siguientes", words[0])));
+        }
+        let left = Box::new(parse_shape(lines, index)?);
+        let right = Box::new(parse_shape(lines, index)?);
+        return Ok(Shape::Csg { op, left, right });
+    }
+
+    let material = words.get(1).ok_or_else(|| error(format!("{} sin material", words[0])))?.to_string();
+    let args = &words[2..];
+    let shape = match words[0] {
+        "sphere" => numbers(args, 4).map(|n| Shape::Sphere { material, center: vec3(&n), radius: n[3] }),
+        "cube" => numbers(args, 6).map(|n| Shape::Cube { material, min: vec3(&n), max: vec3(&n[3..]) }),
+        "cylinder" => numbers(args, 5).map(|n| Shape::Cylinder { material, base: vec3(&n), radius: n[3], height: n[4] }),
+        "cone" => numbers(args, 5).map(|n| Shape::Cone { material, base: vec3(&n), radius: n[3], height: n[4] }),
+        "torus" => numbers(args, 5).map(|n| Shape::Torus { material, center: vec3(&n), major_radius: n[3], minor_radius: n[4] }),
+        "capsule" => numbers(args, 7).map(|n| Shape::Capsule { material, start: vec3(&n), end: vec3(&n[3..]), radius: n[6] }),
+        other => Err(format!("instrucción desconocida: {}", other)),
+    };
+    shape.map_err(error)
+}
+
+impl Scene {
+    pub fn load(path: &str) -> Result<Scene, String> {
+        let text = fs::read_to_string(path).map_err(|error| format!("{}: {}", path, error))?;
+        Scene::parse(&text, path)
+    }
+
+    // Formato de texto, una instrucción por línea; la sangría es solo decorativa:
+    //   material <nombre> <r g b> <brillo> <albedo 0 1 2 3> <índice de refracción> [<absorción r g b> <densidad>]
+    //   subsurface <radio> <r g b>           (dispersión bajo la superficie del último material)
+    //   sun                                  (el sol del ciclo de día y noche)
+    //   light <posición x y z> <r g b> <intensidad>
+    //   camera <campo de visión en grados> [<aspecto del sensor>]
+    //   fog <densidad> <caída con la altura> <altura base> <r g b> <anisotropía> <neblina al amanecer>
+    //   sphere <material> <centro x y z> <radio>
+    //   cube <material> <mínimo x y z> <máximo x y z>
+    //   cylinder | cone <material> <base x y z> <radio> <altura>
+    //   torus <material> <centro x y z> <radio mayor> <radio menor>
+    //   capsule <material> <inicio x y z> <fin x y z> <radio>
+    //   union | intersection | difference   (sus operandos son las dos figuras siguientes)
+    //   translate <x y z> | rotate <grados x y z> | scale <x y z> | swing <amplitud> <velocidad>
+    // Las transformaciones se aplican al último objeto.
+    pub fn parse(text: &str, source: &str) -> Result<Scene, String> {
+        let lines: Vec<(usize, Vec<&str>)> = text.lines()
+            .enumerate()
+            .map(|(number, line)| (number + 1, line.split('#').next().unwrap_or("").split_whitespace().collect::<Vec<&str>>()))
+            .filter(|(_, words)| !words.is_empty())
+            .collect();
+        let located = |(number, message): (usize, String)| format!("{}:{}: {}", source, number, message);
+
+        let mut scene = Scene { materials: Vec::new(), lights: Vec::new(), fog: None, fov: None, aspect_ratio: None, objects: Vec::new() };
+        let mut index = 0;
+        while index < lines.len() {
+            let (number, words) = &lines[index];
+            let error = |message: String| located((*number, message));
+
+            match words[0] {
+                "material" => {
+                    let name = words.get(1).ok_or_else(|| error("material sin nombre".to_string()))?;
+                    // La absorción es opcional: cuatro números más al final
+                    let n = numbers(&words[2..], if words.len() == 15 { 13 } else { 9 }).map_err(error)?;
+                    let channel = |value: f32| value.clamp(0.0, 255.0) as u8;
+                    let mut material = Material::new(
+                        Color::new(channel(n[0]), channel(n[1]), channel(n[2])),
+                        n[3],
+                        [n[4], n[5], n[6], n[7]],
+                        n[8],
+                        name,
+                    );
+                    if let [r, g, b, density] = n[9..] {
+                        if density < 0.0 {
+                            return Err(error("la densidad de absorción no puede ser negativa".to_string()));
+                        }
+                        material = material.with_absorption(Color::new(channel(r), channel(g), channel(b)), density);
+                    }
+                    scene.materials.push(material);
+                    index += 1;
+                }
+                "subsurface" => {
+                    let material = scene.materials.last_mut().ok_or_else(|| error("subsurface antes de cualquier material".to_string()))?;
+                    let n = numbers(&words[1..], 4).map_err(error)?;
+                    if n[0] <= 0.0 {
+                        return Err(error("el radio de subsurface debe ser mayor que cero".to_string()));
+                    }
+                    let channel = |value: f32| value.clamp(0.0, 255.0) as u8;
+                    *material = material.clone().with_subsurface(n[0], Color::new(channel(n[1]), channel(n[2]), channel(n[3])));
+                    index += 1;
+                }
+                "sun" => {
+                    numbers(&words[1..], 0).map_err(error)?;
+                    scene.lights.push(LightSource::Sun);
+                    index += 1;
+                }
+                "light" => {
+                    let n = numbers(&words[1..], 7).map_err(error)?;
+                    let channel = |value: f32| value.clamp(0.0, 255.0) as u8;
+                    let color = Color::new(channel(n[3]), channel(n[4]), channel(n[5]));
+                    scene.lights.push(LightSource::Point(Light::new(vec3(&n), color, n[6])));
+                    index += 1;
+                }
+                "camera" => {
+                    let n = numbers(&words[1..], if words.len() == 3 { 2 } else { 1 }).map_err(error)?;
+                    if n[0] <= 0.0 || n[0] >= 180.0 {
+                        return Err(error("el campo de visión debe estar entre 0 y 180 grados".to_string()));
+                    }
+                    if n.get(1).is_some_and(|&aspect| aspect <= 0.0) {
+                        return Err(error("el aspecto del sensor debe ser mayor que cero".to_string()));
+                    }
+                    scene.fov = Some(n[0].to_radians());
+                    scene.aspect_ratio = n.get(1).copied();
+                    index += 1;
+                }
+                "fog" => {
+                    let n = numbers(&words[1..], 8).map_err(error)?;
+                    if n[0] < 0.0 || n[7] < 0.0 {
+                        return Err(error("la densidad de la niebla no puede ser negativa".to_string()));
+                    }
+                    let channel = |value: f32| value.clamp(0.0, 255.0) as u8;
+                    scene.fog = Some(Fog {
+                        density: n[0],
+                        height_falloff: n[1],
+                        base_height: n[2],
+                        color: Color::new(channel(n[3]), channel(n[4]), channel(n[5])),
+                        anisotropy: n[6].clamp(-1.0, 1.0),
+                        morning_mist: n[7],
+                    });
+                    index += 1;
+                }
+                "translate" | "rotate" | "scale" | "swing" => {
+                    let object = scene.objects.last_mut().ok_or_else(|| error(format!("{} antes de cualquier objeto", words[0])))?;
+                    match words[0] {
+                        "translate" => object.translation = vec3(&numbers(&words[1..], 3).map_err(error)?),
+                        "rotate" => object.rotation = vec3(&numbers(&words[1..], 3).map_err(error)?),
+                        "scale" => {
+                            let scale = vec3(&numbers(&words[1..], 3).map_err(error)?);
+                            if scale.iter().any(|&factor| factor == 0.0) {
+                                return Err(error("la escala no puede ser cero en ningún eje".to_string()));
+                            }
+                            object.scale = scale;
+                        }
+                        _ => {
+                            let n = numbers(&words[1..], 2).map_err(error)?;
+                            object.swing = Some(Swing { amplitude: n[0], speed: n[1] });
+                        }
+                    }
+                    if instance::inverse(&object.transform(0.0)).is_none() {
+                        return Err(error(SINGULAR_TRANSFORM.to_string()));
+                    }
+                    index += 1;
+                }
+                _ => {
+                    let first_line = *number;
+                    let shape = parse_shape(&lines, &mut index).map_err(located)?;
+                    if let Some(missing) = shape.materials().into_iter().find(|name| scene.material(name).is_none()) {
+                        return Err(located((first_line, format!("material desconocido: {}", missing))));
+                    }
+                    scene.objects.push(SceneObject::new(shape));
+                }
+            }
+        }
+
+        Ok(scene)
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = String::from("# material <nombre> <r g b> <brillo> <albedo 0 1 2 3> <índice de refracción> [<absorción r g b> <densidad>]\n");
+        for material in &self.materials {
+            let [r, g, b] = material.color.to_rgb();
+            let [a0, a1, a2, a3] = material.albedo;
+            text.push_str(&format!(
+                "material {} {} {} {} {} {} {} {} {} {}",
+                material.name, r, g, b, material.shininess, a0, a1, a2, a3, material.refractive_index
+            ));
+            let absorption = material.absorption;
+            if absorption.density > 0.0 {
+                let [r, g, b] = absorption.color.to_rgb();
+                text.push_str(&format!(" {} {} {} {}", r, g, b, absorption.density));
+            }
+            text.push('\n');
+            if let Some(subsurface) = material.subsurface {
+                let [r, g, b] = subsurface.color.to_rgb();
+                text.push_str(&format!("  subsurface {} {} {} {}\n", subsurface.radius, r, g, b));
+            }
+        }
+        if !self.lights.is_empty() || self.fog.is_some() || self.fov.is_some() {
+            text.push('\n');
+        }
+        if let Some(fov) = self.fov {
+            match self.aspect_ratio {
+                Some(aspect_ratio) => text.push_str(&format!("camera {} {}\n", fov.to_degrees(), aspect_ratio)),
+                None => text.push_str(&format!("camera {}\n", fov.to_degrees())),
+            }
+        }
+        for light in &self.lights {
+            match light {
+                LightSource::Sun => text.push_str("sun\n"),
+                LightSource::Point(light) => {
+                    let [r, g, b] = light.color.to_rgb();
+                    let p = light.position;
+                    text.push_str(&format!("light {} {} {} {} {} {} {}\n", p.x, p.y, p.z, r, g, b, light.intensity));
+                }
+            }
+        }
+        if let Some(fog) = &self.fog {
+            let [r, g, b] = fog.color.to_rgb();
+            text.push_str(&format!(
+                "fog {} {} {} {} {} {} {} {}\n",
+                fog.density, fog.height_falloff, fog.base_height, r, g, b, fog.anisotropy, fog.morning_mist
+            ));
+        }
+        for object in &self.objects {
+            text.push('\n');
+            object.write(&mut text);
+        }
+        text
+    }
+
+    pub fn save(&self, path: &str) -> std::io::Result<()> {
+        fs::write(path, self.to_text())
+    }
+
+    pub fn material(&self, name: &str) -> Option<&Material> {
+        self.materials.iter().find(|material| material.name == name)
+    }
+
+    // Un objeto por entrada de `objects`, en el mismo orden, para que los índices coincidan
+    pub fn build(&self) -> Result<Vec<Box<dyn RayIntersect>>, String> {
+        self.objects.iter()
+            .enumerate()
+            .map(|(index, object)| object.build(&self.materials).map_err(|error| format!("objeto {}: {}", index, error)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FULL_SCENE: &str = "\
+# Un poco de todo lo que admite el formato
+material piel 200 50 50 50 0.4 0.4 0.2 0 0
+  subsurface 0.2 255 110 80
+material vidrio 240 240 255 120 0 0.5 0.05 1 1.5 60 200 120 1.25
+material suelo 34 139 34 10 0.6 0.2 0 0 0
+
+sun
+light -3 2 4 120 160 255 0.75
+camera 60 1.78
+fog 0.02 0.5 -2 220 225 235 0.4 2
+
+sphere piel 0 1 0 0.5
+  translate 0.85 0.5 -0.25
+  rotate 10 20 30
+  scale 1 2 0.5
+  swing 15 3
+cube suelo -10 -2.1 -10 10 -2 10
+difference
+  sphere vidrio -1.6 -1.7 1.2 0.3
+  union
+    torus piel 0 -0.6 0 0.72 0.06
+    capsule suelo 2 -1.8 -1.5 3.5 -1.8 -0.5 0.2
+cylinder vidrio -3 -2 -2 0.2 1.5
+cone suelo -3 -0.8 -2 0.8 1.8
+";
+
+    fn shape_text(shape: &Shape) -> String {
+        let mut text = String::new();
+        shape.write(&mut text, 0);
+        text
+    }
+
+    fn assert_same_scene(a: &Scene, b: &Scene) {
+        assert_eq!(a.materials.len(), b.materials.len());
+        for (a, b) in a.materials.iter().zip(&b.materials) {
+            assert_eq!(a.name, b.name);
+            assert_eq!(a.color.to_rgb(), b.color.to_rgb());
+            assert_eq!((a.shininess, a.albedo, a.refractive_index), (b.shininess, b.albedo, b.refractive_index));
+            assert_eq!(a.absorption.color.to_rgb(), b.absorption.color.to_rgb());
+            assert_eq!(a.absorption.density, b.absorption.density);
+            assert_eq!(a.subsurface.map(|s| (s.radius, s.color.to_rgb())), b.subsurface.map(|s| (s.radius, s.color.to_rgb())));
+        }
+
+        assert_eq!(a.lights.len(), b.lights.len());
+        for (a, b) in a.lights.iter().zip(&b.lights) {
+            match (a, b) {
+                (LightSource::Sun, LightSource::Sun) => {}
+                (LightSource::Point(a), LightSource::Point(b)) => {
+                    assert_eq!((a.position, a.color.to_rgb(), a.intensity), (b.position, b.color.to_rgb(), b.intensity));
+                }
+                _ => panic!("las luces no coinciden"),
+            }
+        }
+
+        let fog = |scene: &Scene| scene.fog.map(|fog| {
+            (fog.density, fog.height_falloff, fog.base_height, fog.color.to_rgb(), fog.anisotropy, fog.morning_mist)
+        });
+        assert_eq!(fog(a), fog(b));
+        assert_eq!((a.fov, a.aspect_ratio), (b.fov, b.aspect_ratio));
+
+        assert_eq!(a.objects.len(), b.objects.len());
+        for (a, b) in a.objects.iter().zip(&b.objects) {
+            assert_eq!(shape_text(&a.shape), shape_text(&b.shape));
+            assert_eq!((a.translation, a.rotation, a.scale), (b.translation, b.rotation, b.scale));
+            assert_eq!(a.swing.map(|s| (s.amplitude, s.speed)), b.swing.map(|s| (s.amplitude, s.speed)));
+        }
+    }
+
+    fn parse_error(text: &str) -> String {
+        match Scene::parse(text, "prueba.txt") {
+            Ok(_) => panic!("la escena debería tener un error"),
+            Err(error) => error,
+        }
+    }
+
+    #[test]
+    fn round_trip_keeps_every_instruction() {
+        let scene = Scene::parse(FULL_SCENE, "prueba.txt").unwrap();
+        assert_eq!((scene.materials.len(), scene.lights.len(), scene.objects.len()), (3, 2, 5));
+        assert!(scene.fog.is_some() && scene.fov.is_some() && scene.aspect_ratio.is_some());
+
+        let text = scene.to_text();
+        let reparsed = Scene::parse(&text, "guardada.txt").unwrap();
+        assert_same_scene(&scene, &reparsed);
+        assert_eq!(text, reparsed.to_text());
+    }
+
+    #[test]
+    fn round_trip_of_the_default_scene() {
+        let scene = Scene::parse(include_str!("../scene.txt"), "scene.txt").unwrap();
+        let reparsed = Scene::parse(&scene.to_text(), "guardada.txt").unwrap();
+        assert_same_scene(&scene, &reparsed);
+    }
+
+    #[test]
+    fn errors_point_to_the_line_in_the_file() {
+        // Los comentarios y las líneas en blanco también cuentan
+        let error = parse_error("# comentario\nmaterial m 1 2 3 4 0 0 0 0 1\n\nsphere otro 0 0 0 1\n");
+        assert_eq!(error, "prueba.txt:4: material desconocido: otro");
+
+        let error = parse_error("material m 1 2 3 4 0 0 0 0 1\nsphere m 0 0 0 1\n  scale 1 0 1\n");
+        assert_eq!(error, "prueba.txt:3: la escala no puede ser cero en ningún eje");
+
+        let error = parse_error("sun\nlight 1 2 3\n");
+        assert_eq!(error, "prueba.txt:2: se esperaban 7 números y hay 3");
+
+        let error = parse_error("sun\ncamera 200\n");
+        assert_eq!(error, "prueba.txt:2: el campo de visión debe estar entre 0 y 180 grados");
+
+        let error = parse_error("translate 1 2 3\n");
+        assert_eq!(error, "prueba.txt:1: translate antes de cualquier objeto");
+    }
+
+    #[test]
+    fn degenerate_scales_are_errors_instead_of_panics() {
+        let scene_with = |scale: &str| format!("material m 1 2 3 4 0 0 0 0 1\nsun\nsphere m 0 0 0 1\n  scale {}\n", scale);
+        let singular = format!("prueba.txt:4: {}", SINGULAR_TRANSFORM);
+        assert_eq!(parse_error(&scene_with("1e-20 1e-20 1e-20")), singular);
+        assert_eq!(parse_error(&scene_with("1e20 1e20 1e20")), singular);
+        assert_eq!(parse_error(&scene_with("nan 1 1")), "prueba.txt:4: número inválido: nan");
+        assert_eq!(parse_error(&scene_with("1 inf 1")), "prueba.txt:4: número inválido: inf");
+
+        // Una escala chica pero representable sigue siendo válida
+        let scene = Scene::parse(&scene_with("1e-5 1 1"), "prueba.txt").unwrap();
+        assert!(scene.build().is_ok());
+
+        // Si una escena ya leída termina con una escala degenerada, construirla da un error
+        // (la recarga en caliente lo muestra en pantalla) en lugar de detener el programa
+        let mut scene = scene;
+        scene.objects[0].scale = Vec3::new(1e-20, 1e-20, 1e-20);
+        assert_eq!(scene.build().err().unwrap(), format!("objeto 0: {}", SINGULAR_TRANSFORM));
+    }
+
+    #[test]
+    fn errors_inside_csg_point_to_the_operand() {
+        let error = parse_error("material m 1 2 3 4 0 0 0 0 1\nunion\n  sphere m 0 0 0 1\n  sphere m 0 0 x 1\n");
+        assert_eq!(error, "prueba.txt:4: número inválido: x");
+
+        // Si falta el segundo operando se señala la última línea del archivo
+        let error = parse_error("material m 1 2 3 4 0 0 0 0 1\nunion\n  sphere m 0 0 0 1\n");
+        assert_eq!(error, "prueba.txt:3: falta un operando de la operación CSG");
+    }
+}
@@ -0,0 +1,77 @@
+use crate::render_mode::RenderMode;
+
+pub const DEFAULT_MAX_DEPTH: u32 = 3;  // Rebotes de reflexión y refracción que se trazan en total
+pub const DEFAULT_REFLECTION_DEPTH: u32 = 3;
+pub const DEFAULT_REFRACTION_DEPTH: u32 = 3;  // Para atravesar dos vidrios, uno dentro del otro, hacen falta cuatro
+pub const DEFAULT_MIN_CONTRIBUTION: f32 = 1.0 / 255.0;  // Por debajo de un nivel de color el rebote no se nota
+pub const DEFAULT_FOG_STEPS: u32 = 0;  // Puntos por rayo en los que se busca la luz que dispersa la niebla; los haces cuestan una sombra por paso
+pub const DEFAULT_SUBSURFACE_SAMPLES: u32 = 8;  // Puntos alrededor del impacto en los materiales translúcidos
+pub const BLUR_SAMPLES: u32 = 8;  // Muestras por píxel con desenfoque de lente o de movimiento
+pub const DAY_DURATION: f32 = 10.0;  // Duración por defecto del ciclo de día y noche en segundos
+
+// Lo que aporta un camino que se corta por los límites de rebotes o por aportar poco
+#[derive(Clone, Copy, PartialEq)]
+pub enum Termination {
+    Black,    // Nada
+    Ambient,  // La luz ambiente, como si desde ahí solo se viera el entorno
+}
+
+pub const TERMINATIONS: [Termination; 2] = [Termination::Black, Termination::Ambient];
+
+impl Termination {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Termination::Black => "black",
+            Termination::Ambient => "ambient",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Result<Termination, String> {
+        TERMINATIONS.iter()
+            .find(|termination| termination.name() == name)
+            .copied()
+            .ok_or_else(|| format!("corte desconocido: {} (disponibles: black, ambient)", name))
+    }
+}
+
+// Parámetros del render que se eligen en la línea de comandos
+#[derive(Clone, Copy)]
+pub struct RenderSettings {
+    pub mode: RenderMode,
+    pub samples: Option<u32>,  // Sin valor: una muestra, o BLUR_SAMPLES si hay desenfoque
+    pub max_depth: u32,
+    pub max_reflection_depth: u32,
+    pub max_refraction_depth: u32,
+    pub min_contribution: f32,  // Peso mínimo de un rayo secundario en el color del píxel
+    pub roulette_depth: Option<u32>,  // Rebote desde el que se aplica ruleta rusa
+    pub termination: Termination,
+    pub fog_steps: u32,  // Con cero la niebla solo atenúa y refleja el cielo, sin haces de luz
+    pub subsurface_samples: u32,  // Con cero los materiales translúcidos se sombrean como los demás
+    pub seed: u32,
+    pub day_duration: f32,
+}
+
+impl Default for RenderSettings {
+    fn default() -> Self {
+        RenderSettings {
+            mode: RenderMode::Shaded,
+            samples: None,
+            max_depth: DEFAULT_MAX_DEPTH,
+            max_reflection_depth: DEFAULT_REFLECTION_DEPTH,
+            max_refraction_depth: DEFAULT_REFRACTION_DEPTH,
+            min_contribution: DEFAULT_MIN_CONTRIBUTION,
+            roulette_depth: None,
+            termination: Termination::Ambient,
+            fog_steps: DEFAULT_FOG_STEPS,
+            subsurface_samples: DEFAULT_SUBSURFACE_SAMPLES,
+            seed: 0,
+            day_duration: DAY_DURATION,
+        }
+    }
+}
+
+impl RenderSettings {
+    pub fn samples(&self, blur: bool) -> u32 {
+        self.samples.unwrap_or(if blur { BLUR_SAMPLES } else { 1 })
+    }
+}
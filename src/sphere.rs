@@ -1,6 +1,8 @@
 
 use nalgebra_glm::{Vec3, dot};
-use crate::ray_intersect::{RayIntersect, Intersect};
+use crate::ray::Ray;
+use crate::ray_intersect::{spherical_uv, RayIntersect, Intersect};
+use crate::stats::{self, Primitive};
 use crate::material::Material;
 
 pub struct Sphere {
@@ -9,28 +11,52 @@ pub struct Sphere {
     pub material: Material,
 }
 
-impl RayIntersect for Sphere {
-    fn ray_intersect(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Intersect {
-        let oc = ray_origin - self.center;
+impl Sphere {
+    fn roots(&self, ray: &Ray) -> Option<(f32, f32)> {
+        let oc = ray.origin - self.center;
 
-        let a = dot(ray_direction, ray_direction);
-        let b = 2.0 * dot(&oc, ray_direction);
+        let a = dot(&ray.direction, &ray.direction);
+        let b = 2.0 * dot(&oc, &ray.direction);
         let c = dot(&oc, &oc) - self.radius * self.radius;
 
         let discriminant = b * b - 4.0 * a * c;
 
         if discriminant > 0.0 {
-            let t = (-b - discriminant.sqrt()) / (2.0 * a);
-            if t > 0.0 {
-                let point = ray_origin + ray_direction * t;
-                let normal = (point - self.center).normalize();
-                let distance = t;
+            let root = discriminant.sqrt();
+            Some(((-b - root) / (2.0 * a), (-b + root) / (2.0 * a)))
+        } else {
+            None
+        }
+    }
+
+    fn hit_at(&self, ray: &Ray, distance: f32) -> Intersect<'_> {
+        let point = ray.at(distance);
+        let normal = (point - self.center).normalize();
+
+        Intersect::hit(distance, point, normal, spherical_uv(&normal), &self.material)
+    }
+}
 
-                return Intersect::hit(distance, point, normal, &self.material);
+impl RayIntersect for Sphere {
+    fn ray_intersect(&self, ray: &Ray) -> Intersect<'_> {
+        stats::record_test(Primitive::Sphere);
+        match self.roots(ray) {
+            Some((t0, _)) if ray.contains(t0) => self.hit_at(ray, t0),
+            Some((_, t1)) if ray.contains(t1) => self.hit_at(ray, t1),
+            _ => Intersect::empty(),
+        }
+    }
 
-            }
+    fn intervals(&self, ray: &Ray) -> Vec<(Intersect<'_>, Intersect<'_>)> {
+        stats::record_test(Primitive::Sphere);
+        match self.roots(ray) {
+            Some((t0, t1)) => vec![(self.hit_at(ray, t0), self.hit_at(ray, t1))],
+            None => Vec::new(),
         }
+    }
 
-        Intersect::empty()
+    fn occluded(&self, ray: &Ray) -> bool {
+        stats::record_test(Primitive::Sphere);
+        self.roots(ray).is_some_and(|(t0, t1)| ray.contains(t0) || ray.contains(t1))
     }
-}
\ No newline at end of file
+}
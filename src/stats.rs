@@ -0,0 +1,238 @@
+use std::cell::Cell;
+
+pub const MAX_TRACKED_DEPTH: usize = 16;  // Los rebotes más profundos se suman al último casillero
+
+#[derive(Clone, Copy)]
+pub enum Primitive {
+    Sphere,
+    Cube,
+    Cylinder,
+    Cone,
+    Torus,
+    Capsule,
+}
+
+const PRIMITIVES: [Primitive; 6] = [
+    Primitive::Sphere,
+    Primitive::Cube,
+    Primitive::Cylinder,
+    Primitive::Cone,
+    Primitive::Torus,
+    Primitive::Capsule,
+];
+
+impl Primitive {
+    pub fn name(self) -> &'static str {
+        match self {
+            Primitive::Sphere => "sphere",
+            Primitive::Cube => "cube",
+            Primitive::Cylinder => "cylinder",
+            Primitive::Cone => "cone",
+            Primitive::Torus => "torus",
+            Primitive::Capsule => "capsule",
+        }
+    }
+}
+
+// Las primitivas no reciben ningún contexto en `RayIntersect`, así que cada una suma sus
+// pruebas en estos contadores y `RenderStats::take` los recoge al final de cada cuadro. Son
+// locales al hilo y sin atómicos para que contar no encarezca el render. Las instancias y los
+// nodos CSG no suman pruebas propias: ya las cuentan las figuras que envuelven.
+struct Counters {
+    primary_rays: Cell<u64>,
+    shadow_rays: Cell<u64>,
+    subsurface_rays: Cell<u64>,
+    reflection_rays: [Cell<u64>; MAX_TRACKED_DEPTH],
+    refraction_rays: [Cell<u64>; MAX_TRACKED_DEPTH],
+    intersection_tests: [Cell<u64>; PRIMITIVES.len()],
+}
+
+thread_local! {
+    static COUNTERS: Counters = const {
+        Counters {
+            primary_rays: Cell::new(0),
+            shadow_rays: Cell::new(0),
+            subsurface_rays: Cell::new(0),
+            reflection_rays: [const { Cell::new(0) }; MAX_TRACKED_DEPTH],
+            refraction_rays: [const { Cell::new(0) }; MAX_TRACKED_DEPTH],
+            intersection_tests: [const { Cell::new(0) }; PRIMITIVES.len()],
+        }
+    };
+}
+
+fn increment(counter: &Cell<u64>) {
+    counter.set(counter.get() + 1);
+}
+
+// Índice del casillero para un rayo de profundidad `depth` (1 para el primer rebote)
+fn depth_slot(depth: u32) -> usize {
+    (depth as usize).clamp(1, MAX_TRACKED_DEPTH) - 1
+}
+
+pub fn record_primary() {
+    COUNTERS.with(|counters| increment(&counters.primary_rays));
+}
+
+pub fn record_shadow() {
+    COUNTERS.with(|counters| increment(&counters.shadow_rays));
+}
+
+pub fn record_subsurface() {
+    COUNTERS.with(|counters| increment(&counters.subsurface_rays));
+}
+
+pub fn record_reflection(depth: u32) {
+    COUNTERS.with(|counters| increment(&counters.reflection_rays[depth_slot(depth)]));
+}
+
+pub fn record_refraction(depth: u32) {
+    COUNTERS.with(|counters| increment(&counters.refraction_rays[depth_slot(depth)]));
+}
+
+pub fn record_test(primitive: Primitive) {
+    COUNTERS.with(|counters| increment(&counters.intersection_tests[primitive as usize]));
+}
+
+// Pruebas de intersección contadas hasta ahora en el cuadro actual, sin reiniciar nada
+pub fn intersection_tests() -> u64 {
+    COUNTERS.with(|counters| counters.intersection_tests.iter().map(Cell::get).sum())
+}
+
+#[derive(Clone, Default)]
+pub struct RenderStats {
+    pub frame_times: Vec<f32>,      // Segundos de render de cada cuadro
+    pub primary_rays: u64,
+    pub shadow_rays: u64,
+    pub subsurface_rays: u64,       // Sondeos alrededor de los materiales con dispersión bajo la superficie
+    pub reflection_rays: Vec<u64>,  // Índice 0 = profundidad 1
+    pub refraction_rays: Vec<u64>,
+    pub intersection_tests: Vec<(&'static str, u64)>,
+}
+
+fn drain(counter: &Cell<u64>) -> u64 {
+    counter.replace(0)
+}
+
+// Quita los ceros del final para no listar profundidades que nunca se alcanzaron
+fn drain_depths(counters: &[Cell<u64>]) -> Vec<u64> {
+    let mut values: Vec<u64> = counters.iter().map(drain).collect();
+    while values.last() == Some(&0) {
+        values.pop();
+    }
+    values
+}
+
+fn add_depths(total: &mut Vec<u64>, other: &[u64]) {
+    if total.len() < other.len() {
+        total.resize(other.len(), 0);
+    }
+    for (total, value) in total.iter_mut().zip(other) {
+        *total += value;
+    }
+}
+
+fn json_list<T: std::fmt::Display>(values: &[T]) -> String {
+    let items: Vec<String> = values.iter().map(|value| value.to_string()).collect();
+    format!("[{}]", items.join(", "))
+}
+
+impl RenderStats {
+    // Recoge lo contado desde la última llamada y pone los contadores en cero
+    pub fn take(frame_time: f32) -> Self {
+        COUNTERS.with(|counters| RenderStats {
+            frame_times: vec![frame_time],
+            primary_rays: drain(&counters.primary_rays),
+            shadow_rays: drain(&counters.shadow_rays),
+            subsurface_rays: drain(&counters.subsurface_rays),
+            reflection_rays: drain_depths(&counters.reflection_rays),
+            refraction_rays: drain_depths(&counters.refraction_rays),
+            intersection_tests: PRIMITIVES.iter()
+                .map(|&primitive| (primitive.name(), drain(&counters.intersection_tests[primitive as usize])))
+                .collect(),
+        })
+    }
+
+    // Acumula las cifras de otro cuadro
+    pub fn add(&mut self, other: &RenderStats) {
+        self.frame_times.extend_from_slice(&other.frame_times);
+        self.primary_rays += other.primary_rays;
+        self.shadow_rays += other.shadow_rays;
+        self.subsurface_rays += other.subsurface_rays;
+        add_depths(&mut self.reflection_rays, &other.reflection_rays);
+        add_depths(&mut self.refraction_rays, &other.refraction_rays);
+        for &(name, count) in &other.intersection_tests {
+            match self.intersection_tests.iter_mut().find(|(existing, _)| *existing == name) {
+                Some((_, total)) => *total += count,
+                None => self.intersection_tests.push((name, count)),
+            }
+        }
+    }
+
+    pub fn total_time(&self) -> f32 {
+        self.frame_times.iter().sum()
+    }
+
+    pub fn total_rays(&self) -> u64 {
+        self.primary_rays
+            + self.shadow_rays
+            + self.subsurface_rays
+            + self.reflection_rays.iter().sum::<u64>()
+            + self.refraction_rays.iter().sum::<u64>()
+    }
+
+    pub fn rays_per_second(&self) -> f32 {
+        self.total_rays() as f32 / self.total_time().max(1e-6)
+    }
+
+    pub fn summary(&self) -> String {
+        let frames = self.frame_times.len().max(1);
+        let mut text = format!(
+            "Cuadros: {}  tiempo total: {:.2} s  promedio: {:.1} ms/cuadro\n",
+            self.frame_times.len(),
+            self.total_time(),
+            self.total_time() / frames as f32 * 1000.0,
+        );
+        text.push_str(&format!(
+            "Rayos: {} ({:.2} M/s)  primarios: {}  sombra: {}  bajo la superficie: {}\n",
+            self.total_rays(),
+            self.rays_per_second() / 1e6,
+            self.primary_rays,
+            self.shadow_rays,
+            self.subsurface_rays,
+        ));
+        text.push_str(&format!("Reflexión por profundidad: {:?}\n", self.reflection_rays));
+        text.push_str(&format!("Refracción por profundidad: {:?}\n", self.refraction_rays));
+        text.push_str("Pruebas de intersección:");
+        for (name, count) in &self.intersection_tests {
+            text.push_str(&format!(" {}={}", name, count));
+        }
+        text
+    }
+
+    pub fn to_json(&self) -> String {
+        let frames = self.frame_times.len().max(1);
+        let tests: Vec<String> = self.intersection_tests.iter()
+            .map(|(name, count)| format!("\"{}\": {}", name, count))
+            .collect();
+
+        let mut json = String::from("{\n");
+        json.push_str(&format!("  \"frames\": {},\n", self.frame_times.len()));
+        json.push_str(&format!("  \"total_time\": {},\n", self.total_time()));
+        json.push_str(&format!("  \"average_frame_time\": {},\n", self.total_time() / frames as f32));
+        json.push_str(&format!("  \"frame_times\": {},\n", json_list(&self.frame_times)));
+        json.push_str(&format!("  \"total_rays\": {},\n", self.total_rays()));
+        json.push_str(&format!("  \"rays_per_second\": {},\n", self.rays_per_second()));
+        json.push_str(&format!("  \"primary_rays\": {},\n", self.primary_rays));
+        json.push_str(&format!("  \"shadow_rays\": {},\n", self.shadow_rays));
+        json.push_str(&format!("  \"subsurface_rays\": {},\n", self.subsurface_rays));
+        json.push_str(&format!("  \"reflection_rays\": {},\n", json_list(&self.reflection_rays)));
+        json.push_str(&format!("  \"refraction_rays\": {},\n", json_list(&self.refraction_rays)));
+        json.push_str(&format!("  \"intersection_tests\": {{{}}}\n", tests.join(", ")));
+        json.push_str("}\n");
+        json
+    }
+
+    pub fn save_json(&self, path: &str) -> std::io::Result<()> {
+        std::fs::write(path, self.to_json())
+    }
+}
@@ -0,0 +1,23 @@
+// Reloj virtual para renderizar sin ventana: el tiempo de cada cuadro sale solo de su
+// número y de los cuadros por segundo, así que dos ejecuciones producen las mismas imágenes.
+#[derive(Clone, Copy)]
+pub struct Timeline {
+    pub start: f32,     // Instante del primer cuadro, en segundos
+    pub duration: f32,  // Segundos que cubre la secuencia
+    pub fps: f32,
+}
+
+impl Timeline {
+    pub fn new(start: f32, duration: f32, fps: f32) -> Self {
+        Timeline { start, duration, fps }
+    }
+
+    // El último instante no se incluye, para que un ciclo completo encadene sin repetir cuadro
+    pub fn frame_count(&self) -> usize {
+        (self.duration * self.fps).round().max(1.0) as usize
+    }
+
+    pub fn time_at(&self, frame: usize) -> f32 {
+        self.start + frame as f32 / self.fps
+    }
+}
@@ -0,0 +1,338 @@
+use nalgebra_glm::{Vec3, dot};
+use std::f32::consts::PI;
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::ray_intersect::{Intersect, RayIntersect};
+use crate::stats::{self, Primitive};
+
+// Toro alrededor del eje Y. `major_radius` va del centro al centro del tubo y
+// `minor_radius` es el grosor del tubo.
+pub struct Torus {
+    pub center: Vec3,
+    pub major_radius: f32,
+    pub minor_radius: f32,
+    pub material: Material,
+}
+
+const EQN_EPS: f64 = 1e-9;
+const TANGENT_COSINE: f32 = 1e-3;  // Por debajo, el rayo solo roza el tubo y el cruce no abre ni cierra un tramo
+
+fn is_zero(x: f64) -> bool {
+    x.abs() < EQN_EPS
+}
+
+// Raíces de c[2] x^2 + c[1] x + c[0]
+fn solve_quadric(c: [f64; 3], roots: &mut Vec<f64>) {
+    let p = c[1] / (2.0 * c[2]);
+    let q = c[0] / c[2];
+    let discriminant = p * p - q;
+
+    if is_zero(discriminant) {
+        roots.push(-p);
+    } else if discriminant > 0.0 {
+        let root = discriminant.sqrt();
+        roots.push(root - p);
+        roots.push(-root - p);
+    }
+}
+
+// Raíces de c[3] x^3 + c[2] x^2 + c[1] x + c[0] (método de Cardano)
+fn solve_cubic(c: [f64; 4], roots: &mut Vec<f64>) {
+    let a = c[2] / c[3];
+    let b = c[1] / c[3];
+    let cc = c[0] / c[3];
+
+    let sq_a = a * a;
+    let p = (-sq_a / 3.0 + b) / 3.0;
+    let q = (2.0 / 27.0 * a * sq_a - a * b / 3.0 + cc) / 2.0;
+    let cb_p = p * p * p;
+    let discriminant = q * q + cb_p;
+
+    let first = roots.len();
+    if is_zero(discriminant) {
+        if is_zero(q) {
+            roots.push(0.0);
+        } else {
+            let u = (-q).cbrt();
+            roots.push(2.0 * u);
+            roots.push(-u);
+        }
+    } else if discriminant < 0.0 {
+        let phi = (-q / (-cb_p).sqrt()).clamp(-1.0, 1.0).acos() / 3.0;
+        let t = 2.0 * (-p).sqrt();
+        roots.push(t * phi.cos());
+        roots.push(-t * (phi + std::f64::consts::PI / 3.0).cos());
+        roots.push(-t * (phi - std::f64::consts::PI / 3.0).cos());
+    } else {
+        let root = discriminant.sqrt();
+        roots.push((root - q).cbrt() - (root + q).cbrt());
+    }
+
+    for root in &mut roots[first..] {
+        *root -= a / 3.0;
+    }
+}
+
+// Raíces de c[4] x^4 + ... + c[0] (método de Ferrari)
+fn solve_quartic(c: [f64; 5]) -> Vec<f64> {
+    let a = c[3] / c[4];
+    let b = c[2] / c[4];
+    let cc = c[1] / c[4];
+    let d = c[0] / c[4];
+
+    // Sustituimos x = y - a/4 para eliminar el término cúbico: y^4 + p y^2 + q y + r = 0
+    let sq_a = a * a;
+    let p = -3.0 / 8.0 * sq_a + b;
+    let q = sq_a * a / 8.0 - a * b / 2.0 + cc;
+    let r = -3.0 / 256.0 * sq_a * sq_a + sq_a * b / 16.0 - a * cc / 4.0 + d;
+
+    let mut roots = Vec::with_capacity(4);
+    if is_zero(r) {
+        solve_cubic([q, p, 0.0, 1.0], &mut roots);
+        roots.push(0.0);
+    } else {
+        let mut resolvent = Vec::with_capacity(3);
+        solve_cubic([r * p / 2.0 - q * q / 8.0, -r, -p / 2.0, 1.0], &mut resolvent);
+        let z = resolvent[0];
+
+        let u = z * z - r;
+        let v = 2.0 * z - p;
+        let u = if is_zero(u) { 0.0 } else if u > 0.0 { u.sqrt() } else { return roots };
+        let v = if is_zero(v) { 0.0 } else if v > 0.0 { v.sqrt() } else { return roots };
+
+        solve_quadric([z - u, if q < 0.0 { -v } else { v }, 1.0], &mut roots);
+        solve_quadric([z + u, if q < 0.0 { v } else { -v }, 1.0], &mut roots);
+    }
+
+    for root in &mut roots {
+        *root -= a / 4.0;
+    }
+    roots
+}
+
+impl Torus {
+    fn coefficients(&self, origin: &Vec3, direction: &Vec3) -> [f64; 5] {
+        let o = origin.cast::<f64>();
+        let d = direction.cast::<f64>();
+        let major2 = (self.major_radius as f64).powi(2);
+        let minor2 = (self.minor_radius as f64).powi(2);
+
+        let dd = d.dot(&d);
+        let od = o.dot(&d);
+        let k = o.dot(&o) + major2 - minor2;
+
+        // (|p|^2 + R^2 - r^2)^2 = 4 R^2 (x^2 + z^2), con p = o + t d
+        [
+            k * k - 4.0 * major2 * (o.x * o.x + o.z * o.z),
+            4.0 * od * k - 8.0 * major2 * (o.x * d.x + o.z * d.z),
+            4.0 * od * od + 2.0 * dd * k - 4.0 * major2 * (d.x * d.x + d.z * d.z),
+            4.0 * dd * od,
+            dd * dd,
+        ]
+    }
+
+    // Cruces ordenados del rayo con la superficie; entre cada par el rayo está dentro del tubo
+    fn crossings(&self, origin: &Vec3, direction: &Vec3) -> Vec<f32> {
+        // Descartamos con la esfera envolvente y acercamos el origen al toro: la cuártica
+        // pierde mucha precisión cuando el origen está lejos.
+        let bound = self.major_radius + self.minor_radius;
+        let a = dot(direction, direction);
+        let b = dot(origin, direction);
+        let discriminant = b * b - a * (dot(origin, origin) - bound * bound);
+        if discriminant < 0.0 {
+            return Vec::new();
+        }
+        let shift = (-b - discriminant.sqrt()) / a;
+        let origin = origin + direction * shift;
+
+        let coefficients = self.coefficients(&origin, direction);
+        let mut roots = solve_quartic(coefficients);
+
+        // Un paso de Newton para pulir la precisión de Ferrari
+        for root in &mut roots {
+            let t = *root;
+            let f = (((coefficients[4] * t + coefficients[3]) * t + coefficients[2]) * t + coefficients[1]) * t + coefficients[0];
+            let df = ((4.0 * coefficients[4] * t + 3.0 * coefficients[3]) * t + 2.0 * coefficients[2]) * t + coefficients[1];
+            if df.abs() > EQN_EPS {
+                *root = t - f / df;
+            }
+        }
+
+        let mut roots: Vec<f32> = roots.into_iter().map(|t| t as f32 + shift).collect();
+        roots.sort_by(|a, b| a.total_cmp(b));
+        roots
+    }
+
+    fn hit_at(&self, local_origin: &Vec3, direction: &Vec3, distance: f32) -> Intersect<'_> {
+        let local = local_origin + direction * distance;
+        let (normal, (u, v)) = self.surface(&local);
+        Intersect::hit(distance, local + self.center, normal, (u, v), &self.material)
+    }
+
+    fn surface(&self, local: &Vec3) -> (Vec3, (f32, f32)) {
+        let radial = (local.x * local.x + local.z * local.z).sqrt();
+        let s = dot(local, local) - self.major_radius * self.major_radius - self.minor_radius * self.minor_radius;
+        let normal = Vec3::new(
+            local.x * s,
+            local.y * (s + 2.0 * self.major_radius * self.major_radius),
+            local.z * s,
+        ).normalize();
+
+        let u = 0.5 + local.z.atan2(local.x) / (2.0 * PI);
+        let v = 0.5 + local.y.atan2(radial - self.major_radius) / (2.0 * PI);
+        (normal, (u, v))
+    }
+}
+
+impl RayIntersect for Torus {
+    fn ray_intersect(&self, ray: &Ray) -> Intersect<'_> {
+        stats::record_test(Primitive::Torus);
+        let local_origin = ray.origin - self.center;
+        let distance = match self.crossings(&local_origin, &ray.direction).into_iter().find(|&t| ray.contains(t)) {
+            Some(t) => t,
+            None => return Intersect::no_hit(),
+        };
+
+        self.hit_at(&local_origin, &ray.direction, distance)
+    }
+
+    // Cada cruce se clasifica como entrada o salida según hacia dónde mira la normal. Una raíz
+    // tangente o doble puede salir una vez, repetida o ninguna, así que no se cuenta con pares.
+    fn intervals(&self, ray: &Ray) -> Vec<(Intersect<'_>, Intersect<'_>)> {
+        stats::record_test(Primitive::Torus);
+        let local_origin = ray.origin - self.center;
+        let mut spans = Vec::new();
+        let mut enter = None;
+        for t in self.crossings(&local_origin, &ray.direction) {
+            let hit = self.hit_at(&local_origin, &ray.direction, t);
+            let facing = dot(&hit.normal, &ray.direction);
+            if facing < -TANGENT_COSINE {
+                enter = enter.or(Some(hit));
+            } else if facing > TANGENT_COSINE {
+                if let Some(enter) = enter.take() {
+                    spans.push((enter, hit));
+                }
+            }
+        }
+        spans
+    }
+
+    fn occluded(&self, ray: &Ray) -> bool {
+        stats::record_test(Primitive::Torus);
+        let local_origin = ray.origin - self.center;
+        self.crossings(&local_origin, &ray.direction).into_iter().any(|t| ray.contains(t))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::Color;
+
+    const TOLERANCE: f32 = 1e-3;
+
+    // Toro de radio 2 con tubo de 0.5 centrado en el origen
+    fn torus() -> Torus {
+        Torus {
+            center: Vec3::zeros(),
+            major_radius: 2.0,
+            minor_radius: 0.5,
+            material: Material::new(Color::black(), 0.0, [1.0, 0.0, 0.0, 0.0], 1.0, "prueba"),
+        }
+    }
+
+    fn assert_roots(found: &[f32], expected: &[f32], tolerance: f32) {
+        assert_eq!(found.len(), expected.len(), "raíces: {:?}, se esperaban {:?}", found, expected);
+        for (found, expected) in found.iter().zip(expected) {
+            assert!((found - expected).abs() < tolerance, "raíces: {:?}, se esperaban {:?}", found, expected);
+        }
+    }
+
+    #[test]
+    fn quartic_with_four_simple_roots() {
+        // (x - 1)(x - 2)(x - 3)(x - 4)
+        let mut roots: Vec<f32> = solve_quartic([24.0, -50.0, 35.0, -10.0, 1.0]).into_iter().map(|root| root as f32).collect();
+        roots.sort_by(|a, b| a.total_cmp(b));
+        assert_roots(&roots, &[1.0, 2.0, 3.0, 4.0], TOLERANCE);
+    }
+
+    #[test]
+    fn ray_along_the_axis_passes_through_the_hole() {
+        let torus = torus();
+        let ray = Ray::new(Vec3::new(0.0, 5.0, 0.0), Vec3::new(0.0, -1.0, 0.0));
+        assert!(torus.crossings(&ray.origin, &ray.direction).is_empty());
+        assert!(!torus.ray_intersect(&ray).is_intersecting);
+    }
+
+    #[test]
+    fn ray_across_the_ring_crosses_the_tube_twice() {
+        let torus = torus();
+        let ray = Ray::new(Vec3::new(-5.0, 0.0, 0.0), Vec3::new(1.0, 0.0, 0.0));
+        assert_roots(&torus.crossings(&ray.origin, &ray.direction), &[2.5, 3.5, 6.5, 7.5], TOLERANCE);
+
+        let hit = torus.ray_intersect(&ray);
+        assert!(hit.is_intersecting);
+        assert!((hit.point - Vec3::new(-2.5, 0.0, 0.0)).magnitude() < TOLERANCE);
+        assert!((hit.normal - Vec3::new(-1.0, 0.0, 0.0)).magnitude() < TOLERANCE);
+    }
+
+    #[test]
+    fn nearly_grazing_ray_finds_close_pairs_of_roots() {
+        // Justo por debajo de la parte de arriba del tubo: entra y sale muy cerca de x = ±2
+        let torus = torus();
+        let height: f32 = 0.499;
+        let half_chord = (0.25 - height * height).sqrt();
+        let ray = Ray::new(Vec3::new(-5.0, height, 0.0), Vec3::new(1.0, 0.0, 0.0));
+        let expected = [3.0 - half_chord, 3.0 + half_chord, 7.0 - half_chord, 7.0 + half_chord];
+        assert_roots(&torus.crossings(&ray.origin, &ray.direction), &expected, TOLERANCE);
+    }
+
+    fn spans(torus: &Torus, ray: &Ray) -> Vec<(f32, f32)> {
+        torus.intervals(ray).iter().map(|(enter, exit)| (enter.distance, exit.distance)).collect()
+    }
+
+    fn assert_spans(found: &[(f32, f32)], expected: &[(f32, f32)]) {
+        assert_eq!(found.len(), expected.len(), "tramos: {:?}, se esperaban {:?}", found, expected);
+        for (found, expected) in found.iter().zip(expected) {
+            assert!((found.0 - expected.0).abs() < TOLERANCE && (found.1 - expected.1).abs() < TOLERANCE,
+                "tramos: {:?}, se esperaban {:?}", found, expected);
+        }
+    }
+
+    #[test]
+    fn intervals_follow_entries_and_exits() {
+        let torus = torus();
+        let across = Ray::new(Vec3::new(-5.0, 0.0, 0.0), Vec3::new(1.0, 0.0, 0.0));
+        assert_spans(&spans(&torus, &across), &[(2.5, 3.5), (6.5, 7.5)]);
+
+        // Raíz tangente sola encima del tubo: no hay volumen que atravesar
+        let tangent = Ray::new(Vec3::new(-5.0, 0.5, 0.0), Vec3::new(1.0, 0.0, 0.0));
+        assert_spans(&spans(&torus, &tangent), &[]);
+    }
+
+    #[test]
+    fn tangent_root_inside_the_tube_does_not_split_the_interval() {
+        // Recorre el tubo por dentro y roza su borde interior en z = 0: tres raíces, un solo tramo
+        let torus = torus();
+        let ray = Ray::new(Vec3::new(1.5, 0.0, -5.0), Vec3::new(0.0, 0.0, 1.0));
+        assert_spans(&spans(&torus, &ray), &[(3.0, 7.0)]);
+    }
+
+    #[test]
+    fn tangent_ray_touches_the_top_of_the_tube() {
+        // Las raíces dobles en x = ±2 pueden salir una o dos veces, pero siempre en su lugar
+        let torus = torus();
+        let ray = Ray::new(Vec3::new(-5.0, 0.5, 0.0), Vec3::new(1.0, 0.0, 0.0));
+        let crossings = torus.crossings(&ray.origin, &ray.direction);
+        assert!(!crossings.is_empty());
+        assert!(crossings.iter().all(|t| (t - 3.0).abs() < 1e-2 || (t - 7.0).abs() < 1e-2), "raíces: {:?}", crossings);
+
+        let hit = torus.ray_intersect(&ray);
+        assert!(hit.is_intersecting);
+        assert!((hit.distance - 3.0).abs() < 1e-2, "distancia {}", hit.distance);
+
+        // Por encima del tubo no hay ninguna raíz
+        let above = Ray::new(Vec3::new(-5.0, 0.51, 0.0), Vec3::new(1.0, 0.0, 0.0));
+        assert!(torus.crossings(&above.origin, &above.direction).is_empty());
+    }
+}
@@ -0,0 +1,480 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use crate::framebuffer::Framebuffer;
+
+// Destino de una secuencia de cuadros; recibe cada cuadro directamente del framebuffer
+pub trait FrameWriter {
+    fn write_frame(&mut self, framebuffer: &Framebuffer) -> io::Result<()>;
+
+    // Cierra el archivo (p. ej. el final del GIF); no se deben escribir más cuadros después
+    fn finish(&mut self) -> io::Result<()>;
+}
+
+// Elige el formato por la extensión: .gif, .y4m, .rgb/.raw o, si no tiene, una carpeta de PPM
+pub fn create(output: &str, width: usize, height: usize, fps: f32) -> io::Result<Box<dyn FrameWriter>> {
+    let extension = std::path::Path::new(output)
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(|extension| extension.to_ascii_lowercase());
+
+    Ok(match extension.as_deref() {
+        Some("gif") => Box::new(GifWriter::new(output, width, height, fps)?),
+        Some("y4m") => Box::new(Y4mWriter::new(output, width, height, fps)?),
+        Some("rgb") | Some("raw") => Box::new(RawWriter::new(output)?),
+        _ => Box::new(ImageSequence::new(output)?),
+    })
+}
+
+fn rgb(pixel: u32) -> [u8; 3] {
+    [(pixel >> 16) as u8, (pixel >> 8) as u8, pixel as u8]
+}
+
+// Una imagen PPM numerada por cuadro dentro de una carpeta
+pub struct ImageSequence {
+    directory: String,
+    frame: usize,
+}
+
+impl ImageSequence {
+    pub fn new(directory: &str) -> io::Result<Self> {
+        std::fs::create_dir_all(directory)?;
+        Ok(ImageSequence { directory: directory.to_string(), frame: 0 })
+    }
+}
+
+impl FrameWriter for ImageSequence {
+    fn write_frame(&mut self, framebuffer: &Framebuffer) -> io::Result<()> {
+        framebuffer.save_ppm(&format!("{}/frame_{:04}.ppm", self.directory, self.frame))?;
+        self.frame += 1;
+        Ok(())
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+// Flujo RGB de 24 bits sin cabecera, cuadro tras cuadro
+pub struct RawWriter {
+    file: BufWriter<File>,
+}
+
+impl RawWriter {
+    pub fn new(path: &str) -> io::Result<Self> {
+        Ok(RawWriter { file: BufWriter::new(File::create(path)?) })
+    }
+}
+
+impl FrameWriter for RawWriter {
+    fn write_frame(&mut self, framebuffer: &Framebuffer) -> io::Result<()> {
+        for &pixel in &framebuffer.buffer {
+            self.file.write_all(&rgb(pixel))?;
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+// Video YUV4MPEG2 sin comprimir (4:4:4, BT.601 de rango limitado), legible por ffmpeg y mpv
+pub struct Y4mWriter {
+    file: BufWriter<File>,
+}
+
+impl Y4mWriter {
+    pub fn new(path: &str, width: usize, height: usize, fps: f32) -> io::Result<Self> {
+        let mut file = BufWriter::new(File::create(path)?);
+        let rate = (fps * 1000.0).round() as u32;
+        writeln!(file, "YUV4MPEG2 W{} H{} F{}:1000 Ip A1:1 C444", width, height, rate)?;
+        Ok(Y4mWriter { file })
+    }
+}
+
+impl FrameWriter for Y4mWriter {
+    fn write_frame(&mut self, framebuffer: &Framebuffer) -> io::Result<()> {
+        let pixels = framebuffer.buffer.len();
+        let mut planes = vec![0u8; pixels * 3];
+        for (i, &pixel) in framebuffer.buffer.iter().enumerate() {
+            let [r, g, b] = rgb(pixel).map(|channel| channel as i32);
+            planes[i] = (((66 * r + 129 * g + 25 * b + 128) >> 8) + 16) as u8;
+            planes[pixels + i] = (((-38 * r - 74 * g + 112 * b + 128) >> 8) + 128) as u8;
+            planes[2 * pixels + i] = (((112 * r - 94 * g - 18 * b + 128) >> 8) + 128) as u8;
+        }
+        self.file.write_all(b"FRAME\n")?;
+        self.file.write_all(&planes)
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+// GIF animado en bucle; cada cuadro lleva su propia paleta de 256 colores
+pub struct GifWriter {
+    file: BufWriter<File>,
+    width: u16,
+    height: u16,
+    delay: u16,  // Centésimas de segundo entre cuadros
+}
+
+impl GifWriter {
+    pub fn new(path: &str, width: usize, height: usize, fps: f32) -> io::Result<Self> {
+        let too_large = |size: usize| size > u16::MAX as usize;
+        if too_large(width) || too_large(height) {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "el GIF admite como máximo 65535 píxeles por lado"));
+        }
+
+        let mut file = BufWriter::new(File::create(path)?);
+        file.write_all(b"GIF89a")?;
+        file.write_all(&(width as u16).to_le_bytes())?;
+        file.write_all(&(height as u16).to_le_bytes())?;
+        file.write_all(&[0, 0, 0])?;  // Sin paleta global, fondo 0, píxeles cuadrados
+
+        // Extensión NETSCAPE2.0 para que la animación se repita sin fin
+        file.write_all(&[0x21, 0xFF, 0x0B])?;
+        file.write_all(b"NETSCAPE2.0")?;
+        file.write_all(&[0x03, 0x01, 0x00, 0x00, 0x00])?;
+
+        Ok(GifWriter {
+            file,
+            width: width as u16,
+            height: height as u16,
+            delay: (100.0 / fps).round().clamp(1.0, u16::MAX as f32) as u16,
+        })
+    }
+}
+
+impl FrameWriter for GifWriter {
+    fn write_frame(&mut self, framebuffer: &Framebuffer) -> io::Result<()> {
+        let (palette, indices) = quantize(&framebuffer.buffer);
+
+        // Control gráfico: reemplazar el cuadro anterior y esperar `delay`
+        self.file.write_all(&[0x21, 0xF9, 0x04, 0x04])?;
+        self.file.write_all(&self.delay.to_le_bytes())?;
+        self.file.write_all(&[0x00, 0x00])?;
+
+        // Descriptor de imagen con paleta local de 256 entradas
+        self.file.write_all(&[0x2C, 0, 0, 0, 0])?;
+        self.file.write_all(&self.width.to_le_bytes())?;
+        self.file.write_all(&self.height.to_le_bytes())?;
+        self.file.write_all(&[0x87])?;
+        for index in 0..256 {
+            self.file.write_all(&palette.get(index).copied().unwrap_or([0, 0, 0]))?;
+        }
+
+        self.file.write_all(&[8])?;
+        for block in lzw_encode(&indices).chunks(255) {
+            self.file.write_all(&[block.len() as u8])?;
+            self.file.write_all(block)?;
+        }
+        self.file.write_all(&[0])
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.file.write_all(&[0x3B])?;
+        self.file.flush()
+    }
+}
+
+// Reduce un cuadro a 256 colores con el algoritmo de corte por la mediana sobre un
+// histograma de 15 bits; devuelve la paleta y el índice de cada píxel.
+fn quantize(pixels: &[u32]) -> (Vec<[u8; 3]>, Vec<u8>) {
+    let bin = |pixel: u32| {
+        let [r, g, b] = rgb(pixel);
+        ((r as usize >> 3) << 10) | ((g as usize >> 3) << 5) | (b as usize >> 3)
+    };
+
+    // Por casilla: cantidad de píxeles y suma de sus colores reales
+    let mut counts = vec![0u32; 1 << 15];
+    let mut sums = vec![[0u64; 3]; 1 << 15];
+    for &pixel in pixels {
+        let index = bin(pixel);
+        counts[index] += 1;
+        for (sum, channel) in sums[index].iter_mut().zip(rgb(pixel)) {
+            *sum += channel as u64;
+        }
+    }
+
+    let channel = |index: usize, axis: usize| (index >> (10 - 5 * axis)) & 31;
+    let mut boxes: Vec<Vec<usize>> = vec![(0..1 << 15).filter(|&index| counts[index] > 0).collect()];
+
+    while boxes.len() < 256 {
+        // Partimos la caja con más error potencial (rango de color por cantidad de píxeles)
+        // que todavía tenga más de una casilla
+        let widest = boxes.iter()
+            .enumerate()
+            .filter(|(_, bins)| bins.len() > 1)
+            .map(|(i, bins)| {
+                let (axis, range) = (0..3)
+                    .map(|axis| {
+                        let values = bins.iter().map(|&index| channel(index, axis));
+                        (axis, values.clone().max().unwrap() - values.min().unwrap())
+                    })
+                    .max_by_key(|&(_, range)| range)
+                    .unwrap();
+                let population: u64 = bins.iter().map(|&index| counts[index] as u64).sum();
+                (i, axis, range as u64 * population)
+            })
+            .max_by_key(|&(_, _, error)| error);
+
+        let (i, axis, _) = match widest {
+            Some(widest) => widest,
+            None => break,
+        };
+
+        let mut bins = boxes.swap_remove(i);
+        bins.sort_by_key(|&index| channel(index, axis));
+        let half: u64 = bins.iter().map(|&index| counts[index] as u64).sum::<u64>() / 2;
+        let mut accumulated = 0;
+        let split = bins.iter()
+            .position(|&index| {
+                accumulated += counts[index] as u64;
+                accumulated >= half
+            })
+            .unwrap_or(0)
+            .clamp(0, bins.len() - 2) + 1;
+        let upper = bins.split_off(split);
+        boxes.push(bins);
+        boxes.push(upper);
+    }
+
+    let mut lookup = vec![0u8; 1 << 15];
+    let palette = boxes.iter()
+        .enumerate()
+        .map(|(i, bins)| {
+            let mut total = [0u64; 3];
+            let mut count = 0u64;
+            for &index in bins {
+                lookup[index] = i as u8;
+                count += counts[index] as u64;
+                for (total, sum) in total.iter_mut().zip(sums[index]) {
+                    *total += sum;
+                }
+            }
+            total.map(|total| (total / count.max(1)) as u8)
+        })
+        .collect();
+
+    (palette, pixels.iter().map(|&pixel| lookup[bin(pixel)]).collect())
+}
+
+// Compresión LZW de GIF con códigos de 9 a 12 bits empaquetados desde el bit menos significativo
+fn lzw_encode(indices: &[u8]) -> Vec<u8> {
+    const CLEAR: u16 = 256;
+    const END: u16 = 257;
+    const MAX_CODES: u16 = 4096;
+
+    let mut output = Vec::new();
+    let mut bits: u32 = 0;
+    let mut bit_count = 0;
+    let mut code_size = 9;
+    let mut emit = |code: u16, code_size: u32, output: &mut Vec<u8>| {
+        bits |= (code as u32) << bit_count;
+        bit_count += code_size;
+        while bit_count >= 8 {
+            output.push(bits as u8);
+            bits >>= 8;
+            bit_count -= 8;
+        }
+    };
+
+    let mut dictionary: HashMap<(u16, u8), u16> = HashMap::new();
+    let mut next_code = END + 1;
+    emit(CLEAR, code_size, &mut output);
+
+    let mut prefix = match indices.first() {
+        Some(&first) => first as u16,
+        None => {
+            emit(END, code_size, &mut output);
+            emit(0, 7, &mut output);
+            return output;
+        }
+    };
+
+    for &index in &indices[1..] {
+        if let Some(&code) = dictionary.get(&(prefix, index)) {
+            prefix = code;
+            continue;
+        }
+
+        emit(prefix, code_size, &mut output);
+        if next_code < MAX_CODES {
+            dictionary.insert((prefix, index), next_code);
+            next_code += 1;
+            if next_code > 1 << code_size && code_size < 12 {
+                code_size += 1;
+            }
+        } else {
+            // Tabla llena: empezamos de nuevo para adaptarnos al resto de la imagen
+            emit(CLEAR, code_size, &mut output);
+            dictionary.clear();
+            next_code = END + 1;
+            code_size = 9;
+        }
+        prefix = index as u16;
+    }
+
+    emit(prefix, code_size, &mut output);
+    emit(END, code_size, &mut output);
+    emit(0, 7, &mut output);  // Completa el último byte
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random::Rng;
+
+    // Descompresión LZW de GIF, la inversa de `lzw_encode`
+    fn lzw_decode(data: &[u8]) -> Vec<u8> {
+        const CLEAR: usize = 256;
+        const END: usize = 257;
+        let initial_table = || -> Vec<Vec<u8>> { (0..=END).map(|code| vec![code as u8]).collect() };
+
+        let mut table = initial_table();
+        let mut code_size = 9;
+        let mut position = 0;
+        let mut previous: Option<usize> = None;
+        let mut output = Vec::new();
+        loop {
+            assert!(position + code_size <= data.len() * 8, "el flujo LZW termina sin código de fin");
+            let code = (0..code_size).fold(0, |code, bit| {
+                let bit_position = position + bit;
+                code | (((data[bit_position / 8] >> (bit_position % 8)) & 1) as usize) << bit
+            });
+            position += code_size;
+
+            match code {
+                CLEAR => {
+                    table = initial_table();
+                    code_size = 9;
+                    previous = None;
+                    continue;
+                }
+                END => return output,
+                _ => {}
+            }
+
+            let entry = match (table.get(code), previous) {
+                (Some(entry), _) => entry.clone(),
+                (None, Some(previous)) => {
+                    let mut entry = table[previous].clone();
+                    entry.push(entry[0]);
+                    entry
+                }
+                (None, None) => panic!("código {} sin entrada en la tabla", code),
+            };
+            if let Some(previous) = previous {
+                if table.len() < 4096 {
+                    let mut new_entry = table[previous].clone();
+                    new_entry.push(entry[0]);
+                    table.push(new_entry);
+                }
+            }
+            if table.len() == 1 << code_size && code_size < 12 {
+                code_size += 1;
+            }
+            output.extend_from_slice(&entry);
+            previous = Some(code);
+        }
+    }
+
+    // Lee los cuadros de un GIF como el que escribe `GifWriter`, ya convertidos a 0xRRGGBB
+    fn decode_gif(bytes: &[u8]) -> (usize, usize, Vec<Vec<u32>>) {
+        assert_eq!(&bytes[..6], b"GIF89a");
+        let width = u16::from_le_bytes([bytes[6], bytes[7]]) as usize;
+        let height = u16::from_le_bytes([bytes[8], bytes[9]]) as usize;
+        let mut position = 13;
+
+        // Une los subbloques de datos a partir de `position` y avanza hasta después del terminador
+        let sub_blocks = |position: &mut usize| {
+            let mut data = Vec::new();
+            while bytes[*position] != 0 {
+                let length = bytes[*position] as usize;
+                data.extend_from_slice(&bytes[*position + 1..*position + 1 + length]);
+                *position += 1 + length;
+            }
+            *position += 1;
+            data
+        };
+
+        let mut frames = Vec::new();
+        loop {
+            match bytes[position] {
+                0x21 => {
+                    position += 2;
+                    sub_blocks(&mut position);
+                }
+                0x2C => {
+                    let flags = bytes[position + 9];
+                    assert_eq!(flags & 0x80, 0x80, "cada cuadro debe traer su paleta");
+                    let palette_size = 2 << (flags & 0x07);
+                    let palette: Vec<u32> = bytes[position + 10..position + 10 + palette_size * 3]
+                        .chunks_exact(3)
+                        .map(|color| (color[0] as u32) << 16 | (color[1] as u32) << 8 | color[2] as u32)
+                        .collect();
+                    position += 10 + palette_size * 3;
+                    assert_eq!(bytes[position], 8);
+                    position += 1;
+
+                    let indices = lzw_decode(&sub_blocks(&mut position));
+                    assert_eq!(indices.len(), width * height);
+                    frames.push(indices.iter().map(|&index| palette[index as usize]).collect());
+                }
+                0x3B => return (width, height, frames),
+                other => panic!("bloque desconocido 0x{:02X}", other),
+            }
+        }
+    }
+
+    fn round_trip(name: &str, frames: &[Framebuffer]) -> (usize, usize, Vec<Vec<u32>>) {
+        let path = std::env::temp_dir().join(format!("raytracing_project_{}_{}.gif", name, std::process::id()));
+        let path = path.to_str().unwrap();
+        let mut writer = GifWriter::new(path, frames[0].width, frames[0].height, 30.0).unwrap();
+        for frame in frames {
+            writer.write_frame(frame).unwrap();
+        }
+        writer.finish().unwrap();
+        drop(writer);
+
+        let bytes = std::fs::read(path).unwrap();
+        std::fs::remove_file(path).unwrap();
+        decode_gif(&bytes)
+    }
+
+    #[test]
+    fn gif_round_trip_keeps_up_to_256_colors_exactly() {
+        // 200 colores en casillas distintas del histograma: el corte por la mediana los separa a
+        // todos. El ruido llena la tabla LZW varias veces, así que también se prueba el reinicio.
+        let colors: Vec<u32> = (0..200u32).map(|i| ((i % 8) * 32) << 16 | ((i / 8 % 8) * 32) << 8 | ((i / 64) * 32)).collect();
+        let mut rng = Rng::new(7);
+        let frames: Vec<Framebuffer> = (0..2)
+            .map(|_| {
+                let mut frame = Framebuffer::new(160, 120);
+                for pixel in frame.buffer.iter_mut() {
+                    *pixel = colors[rng.next_u32() as usize % colors.len()];
+                }
+                frame
+            })
+            .collect();
+
+        let (width, height, decoded) = round_trip("colors", &frames);
+        assert_eq!((width, height), (160, 120));
+        assert_eq!(decoded.len(), frames.len());
+        for (decoded, frame) in decoded.iter().zip(&frames) {
+            assert!(decoded == &frame.buffer, "los píxeles no coinciden después de decodificar");
+        }
+    }
+
+    #[test]
+    fn gif_round_trip_of_a_single_color_frame() {
+        let mut frame = Framebuffer::new(33, 17);
+        frame.buffer.fill(0x3366CC);
+        let (_, _, decoded) = round_trip("single", &[frame]);
+        assert_eq!(decoded.len(), 1);
+        assert!(decoded[0].iter().all(|&pixel| pixel == 0x3366CC));
+    }
+}